@@ -0,0 +1,141 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::{Result, bail};
+use notify_rust::ActionResponse;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::process::Command;
+use std::sync::Mutex;
+use std::thread;
+use tracing::{debug, error, info, instrument, warn};
+
+use crate::config::Action;
+use crate::fsm::State;
+use crate::{Config, history, persist, util};
+
+// The notifications whose actions are listened to, by id, with the state
+// and snooze duration of their current content. A notification replaced
+// in place keeps its id, the entry is updated
+static LISTENING: Lazy<Mutex<HashMap<u32, Target>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+// What the actions of a notification apply to
+#[derive(Debug, Clone, Copy)]
+struct Target {
+    state: Option<State>,
+    snooze: u32,
+}
+
+// States dismissed by the user until bato exits
+static DISMISSED: Lazy<Mutex<HashSet<State>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
+/// The label of the action button
+pub fn label(config: &Config, action: Action) -> String {
+    match action {
+        Action::Snooze => format!("Snooze {} min", config.snooze),
+        Action::Suspend => "Suspend now".to_string(),
+        Action::Hibernate => "Hibernate now".to_string(),
+        Action::Dismiss => "Don't remind again this session".to_string(),
+    }
+}
+
+pub fn is_dismissed(state: State) -> bool {
+    DISMISSED
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains(&state)
+}
+
+/// Listen to the actions invoked on a notification, in a dedicated thread
+/// as waiting for the `ActionInvoked` signal blocks until the
+/// notification is closed
+#[instrument(skip(config))]
+pub fn listen(config: &Config, id: u32, state: Option<State>) -> Result<()> {
+    // a replaced notification keeps its id and its listener, which now acts
+    // on the new content
+    if LISTENING
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(
+            id,
+            Target {
+                state,
+                snooze: config.snooze,
+            },
+        )
+        .is_some()
+    {
+        return Ok(());
+    }
+    thread::Builder::new()
+        .name("action_handler".into())
+        .spawn(move || {
+            notify_rust::handle_action(id, |response: &ActionResponse| match response {
+                ActionResponse::Custom(key) => {
+                    info!("action {key} invoked on notification {id}");
+                    let Some(target) = LISTENING
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .get(&id)
+                        .copied()
+                    else {
+                        return;
+                    };
+                    handle(key, target.state, target.snooze)
+                        .inspect_err(|e| error!("failed to handle action {key}: {e}"))
+                        .ok();
+                }
+                ActionResponse::Closed(reason) => {
+                    debug!("notification {id} closed: {reason:?}");
+                }
+            })
+            .inspect_err(|e| error!("failed to listen to actions: {e}"))
+            .ok();
+            LISTENING
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .remove(&id);
+        })?;
+    Ok(())
+}
+
+fn handle(key: &str, state: Option<State>, snooze: u32) -> Result<()> {
+    let action = match key {
+        "snooze" => Action::Snooze,
+        "suspend" => Action::Suspend,
+        "hibernate" => Action::Hibernate,
+        "dismiss" => Action::Dismiss,
+        // a click on the notification body
+        "default" => return Ok(()),
+        _ => bail!("unknown action {key}"),
+    };
+    match action {
+        Action::Snooze => {
+            let until = util::now() + snooze as u64 * 60;
+            info!("notifications snoozed for {snooze} min");
+            history::get().snoozed_until = Some(until);
+            // the snooze holds across restarts
+            persist::request();
+        }
+        Action::Dismiss => match state {
+            Some(state) => {
+                info!("{state} notifications dismissed for this session");
+                DISMISSED
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert(state);
+            }
+            None => warn!("no state to dismiss"),
+        },
+        Action::Suspend | Action::Hibernate => {
+            let verb = action.as_ref();
+            info!("{verb} the system");
+            let status = Command::new("systemctl").arg(verb).status()?;
+            if !status.success() {
+                bail!("systemctl {verb} failed: {status}");
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,328 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::{Result, anyhow, bail};
+use mio::event::Source;
+use mio::unix::SourceFd;
+use mio::{Events, Interest, Poll, Registry, Token};
+use serde::Deserialize;
+use serde_json::json;
+use std::fs;
+use std::io::{self, BufRead, BufReader, ErrorKind, Write};
+use std::os::fd::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Duration;
+use tracing::{debug, error, info, instrument, trace};
+
+use crate::fsm::{PsStatus, State};
+use crate::template::{self, Value};
+use crate::{Bato, Config, Status, UDEV_SUBSYSTEM, ac_event, runtime, tick, util};
+
+const SOCKET: &str = "bar.sock";
+const DEFAULT_TEXT: &str = "{icon} {level}%";
+
+/// How the status is printed for the bar
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum BarFormat {
+    /// One JSON object per line, for a waybar custom module
+    Waybar,
+    /// The i3bar protocol, for i3bar and swaybar
+    I3bar,
+    /// A template, one line per update, eg. for polybar
+    Custom(String),
+}
+
+impl FromStr for BarFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some(("custom", template)) => Ok(BarFormat::Custom(template.to_string())),
+            None if s == "waybar" => Ok(BarFormat::Waybar),
+            None if s == "i3bar" => Ok(BarFormat::I3bar),
+            _ => Err(format!(
+                "unknown format `{s}`, expected waybar, i3bar or custom=TEMPLATE"
+            )),
+        }
+    }
+}
+
+fn socket_path() -> PathBuf {
+    runtime::runtime_dir().join(SOCKET)
+}
+
+/// Serves the status to the `bato bar` clients, a JSON line per update
+#[derive(Debug)]
+pub struct Publisher {
+    listener: UnixListener,
+    clients: Vec<UnixStream>,
+    // the last status, sent to the new clients
+    last: Option<String>,
+}
+
+impl Publisher {
+    #[instrument]
+    pub fn bind() -> Result<Self> {
+        let path = socket_path();
+        util::check_dir_or_create(&runtime::runtime_dir())?;
+        // left behind by a bato that did not exit cleanly
+        fs::remove_file(&path).ok();
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+        debug!("bar socket {}", path.display());
+        Ok(Publisher {
+            listener,
+            clients: vec![],
+            last: None,
+        })
+    }
+
+    /// Accept the pending clients
+    pub fn accept(&mut self) {
+        loop {
+            match self.listener.accept() {
+                Ok((mut stream, _)) => {
+                    info!("bar client connected");
+                    // a stuck client must not block the main-loop
+                    stream.set_nonblocking(true).ok();
+                    let sent = match &self.last {
+                        Some(line) => stream.write_all(line.as_bytes()).is_ok(),
+                        None => true,
+                    };
+                    if sent {
+                        self.clients.push(stream);
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    error!("failed to accept bar client: {e}");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Send the status to the clients, dropping the gone ones
+    pub fn publish(&mut self, status: &Status) {
+        let Ok(json) = serde_json::to_string(status) else {
+            return;
+        };
+        let line = format!("{json}\n");
+        self.clients.retain_mut(|client| {
+            client
+                .write_all(line.as_bytes())
+                .inspect_err(|e| debug!("bar client dropped: {e}"))
+                .is_ok()
+        });
+        self.last = Some(line);
+    }
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        fs::remove_file(socket_path()).ok();
+    }
+}
+
+impl Source for Publisher {
+    fn register(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.listener.as_raw_fd()).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.listener.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        SourceFd(&self.listener.as_raw_fd()).deregister(registry)
+    }
+}
+
+/// Print the status for a status bar on every change. Follows the running
+/// bato if any, then reads the battery itself
+#[instrument(skip(config))]
+pub fn run(config: Config, format: BarFormat) -> Result<()> {
+    let mut output = Output::new(format)?;
+    match UnixStream::connect(socket_path()) {
+        Ok(stream) => {
+            info!("attached to the running bato");
+            follow(stream, &mut output)?;
+            info!("bato stopped, reading the battery");
+        }
+        Err(e) => debug!("no running bato: {e}"),
+    }
+    standalone(config, &mut output)
+}
+
+// print the statuses sent by the daemon, until it stops
+fn follow(stream: UnixStream, output: &mut Output) -> Result<()> {
+    for line in BufReader::new(stream).lines() {
+        let status: Status = serde_json::from_str(&line?)?;
+        output.print(&status)?;
+    }
+    Ok(())
+}
+
+// read the battery on each tick and AC event, without notifying
+fn standalone(config: Config, output: &mut Output) -> Result<()> {
+    const MONITOR: Token = Token(0);
+    let mut bato = Bato::with_config(config)?;
+    let mut poll = Poll::new()?;
+    let mut events = Events::with_capacity(16);
+    let mut socket = udev::MonitorBuilder::new()?
+        .match_subsystem(UDEV_SUBSYSTEM)?
+        .listen()?;
+    poll.registry()
+        .register(&mut socket, MONITOR, Interest::READABLE)?;
+    let mut ac = None;
+    loop {
+        let status = bato.status(ac)?;
+        output.print(&status)?;
+        let tick = tick::next_tick(&bato.config, None);
+        trace!("next tick in {}s", tick.as_secs());
+        match poll.poll(&mut events, Some(tick)) {
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => bail!("poll error: {e}"),
+        }
+        ac = match events.is_empty() {
+            true => None,
+            false => ac_event(&socket),
+        };
+    }
+}
+
+// prints a line per change of the rendered status
+struct Output {
+    format: BarFormat,
+    last: Option<String>,
+}
+
+impl Output {
+    fn new(format: BarFormat) -> Result<Self> {
+        if format == BarFormat::I3bar {
+            // the header then the opening of an endless array
+            println!("{}\n[", json!({ "version": 1 }));
+        }
+        Ok(Output { format, last: None })
+    }
+
+    fn print(&mut self, status: &Status) -> Result<()> {
+        let line = self.render(status);
+        if self.last.as_ref() == Some(&line) {
+            return Ok(());
+        }
+        let mut stdout = io::stdout().lock();
+        match (&self.format, &self.last) {
+            (BarFormat::I3bar, Some(_)) => writeln!(stdout, ",{line}"),
+            _ => writeln!(stdout, "{line}"),
+        }
+        .and_then(|_| stdout.flush())
+        .map_err(|e| anyhow!("failed to print the status: {e}"))?;
+        self.last = Some(line);
+        Ok(())
+    }
+
+    fn render(&self, status: &Status) -> String {
+        let lookup = |name: &str| placeholder(name, status);
+        match &self.format {
+            BarFormat::Waybar => json!({
+                "text": template::render(DEFAULT_TEXT, lookup),
+                "tooltip": tooltip(status),
+                "class": class(status.state),
+                "alt": class(status.state),
+                "percentage": status.level,
+            })
+            .to_string(),
+            BarFormat::I3bar => json!([{
+                "name": "bato",
+                "full_text": template::render(DEFAULT_TEXT, lookup),
+                "urgent": status.is_critical(),
+            }])
+            .to_string(),
+            BarFormat::Custom(text) => template::render(text, lookup),
+        }
+    }
+}
+
+// the value of a placeholder of the bar text, `None` if it is unknown
+fn placeholder(name: &str, status: &Status) -> Option<Option<Value>> {
+    let value = match name {
+        "icon" => Some(Value::Str(icon(status).to_string())),
+        "level" => Some(Value::Int(status.level as i64)),
+        "status" => Some(Value::Str(status.status.as_ref().to_string())),
+        "state" => Some(Value::Str(status.state.to_string())),
+        "time_remaining" => status
+            .time_remaining
+            .map(|t| Value::Str(util::format_duration(Duration::from_secs(t)))),
+        "power_w" => status.power_w.map(Value::Float),
+        "battery" => Some(Value::Str(status.battery.clone())),
+        "health" => status.health.map(|h| Value::Int(h as i64)),
+        "temperature" => status.temperature.map(Value::Float),
+        _ => return None,
+    };
+    Some(value)
+}
+
+// a Font Awesome battery glyph, as used by waybar
+fn icon(status: &Status) -> &'static str {
+    if status.status == PsStatus::Charging {
+        return "\u{f1e6}";
+    }
+    match status.level {
+        0..=12 => "\u{f244}",
+        13..=37 => "\u{f243}",
+        38..=62 => "\u{f242}",
+        63..=87 => "\u{f241}",
+        _ => "\u{f240}",
+    }
+}
+
+// the CSS class of a state
+fn class(state: State) -> &'static str {
+    match state {
+        State::Charging => "charging",
+        State::Discharging => "discharging",
+        State::NotCharging => "not-charging",
+        State::Full => "full",
+        State::Low => "low",
+        State::Critical => "critical",
+    }
+}
+
+fn tooltip(status: &Status) -> String {
+    let mut lines = vec![format!(
+        "{}: {}%, {}",
+        status.battery,
+        status.level,
+        status.status.as_ref()
+    )];
+    if let Some(t) = status.time_remaining {
+        let until = match status.status {
+            PsStatus::Charging => "until full",
+            _ => "remaining",
+        };
+        let time = util::format_duration(Duration::from_secs(t));
+        lines.push(format!("{time} {until}"));
+    }
+    if let Some(power) = status.power_w {
+        lines.push(format!("{power:.1} W"));
+    }
+    if let Some(health) = status.health {
+        lines.push(format!("health {health}%"));
+    }
+    lines.join("\n")
+}
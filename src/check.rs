@@ -0,0 +1,453 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::{Context, Result};
+use std::fmt::Write;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use toml::de::{DeTable, DeValue};
+use toml::{Spanned, Table, Value};
+
+use crate::config::{self, Config, UserConfig};
+use crate::util;
+
+/// A config file, layered over the previous ones
+#[derive(Debug)]
+pub struct Layer {
+    pub path: PathBuf,
+    pub content: String,
+}
+
+/// A problem found in a config, located when possible
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub message: String,
+    /// Index of the layer at fault
+    pub layer: Option<usize>,
+    pub span: Option<Range<usize>>,
+}
+
+impl Layer {
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("failed to read config file {}", path.display()))?;
+        Ok(Layer {
+            path: path.to_path_buf(),
+            content,
+        })
+    }
+}
+
+/// Parse, merge and validate the layers of a config
+pub fn check(layers: &[Layer]) -> Result<Config, Vec<Diagnostic>> {
+    let (config, base) = match layers {
+        [layer] => {
+            let config: UserConfig = toml::from_str(&layer.content).map_err(|e| {
+                vec![Diagnostic {
+                    message: suggest(e.message()),
+                    layer: Some(0),
+                    span: e.span(),
+                }]
+            })?;
+            (config, layer.content.parse().unwrap_or_default())
+        }
+        _ => merge(layers)?,
+    };
+    let config = Config {
+        base,
+        ..Config::from(config)
+    };
+    let problems = config.problems();
+    if problems.is_empty() {
+        return check_profiles(config, layers);
+    }
+    let documents = parse_documents(layers);
+    let diagnostics = problems
+        .into_iter()
+        .map(|(key, message)| {
+            // blame the last layer setting the key
+            let location = documents.iter().enumerate().rev().find_map(|(i, d)| {
+                d.get_ref()
+                    .iter()
+                    .find(|(k, _)| k.get_ref() == key)
+                    .map(|(k, _)| (i, k.span()))
+            });
+            Diagnostic {
+                message,
+                layer: location.as_ref().map(|(i, _)| *i),
+                span: location.map(|(_, span)| span),
+            }
+        })
+        .collect();
+    Err(diagnostics)
+}
+
+// check the config resulting from each profile
+fn check_profiles(config: Config, layers: &[Layer]) -> Result<Config, Vec<Diagnostic>> {
+    let failed: Vec<(&str, String)> = config
+        .profiles
+        .iter()
+        .filter_map(|p| {
+            config
+                .profiled(std::slice::from_ref(&p.name))
+                .err()
+                .map(|e| (p.name.as_str(), suggest(&e.to_string())))
+        })
+        .collect();
+    if failed.is_empty() {
+        return Ok(config);
+    }
+    let documents = parse_documents(layers);
+    let diagnostics = failed
+        .into_iter()
+        .map(|(name, message)| {
+            // blame the last layer defining the profile, at the unknown or
+            // invalid key when the profile sets it
+            let key = unknown_field(&message).or_else(|| {
+                message
+                    .strip_prefix("invalid config: ")
+                    .and_then(|m| m.split(' ').next())
+            });
+            let location =
+                documents.iter().enumerate().rev().find_map(|(i, d)| {
+                    find_profile_key(d.get_ref(), name, key).map(|span| (i, span))
+                });
+            Diagnostic {
+                message: format!("profile `{name}`: {message}"),
+                layer: location.as_ref().map(|(i, _)| *i),
+                span: location.map(|(_, span)| span),
+            }
+        })
+        .collect();
+    Err(diagnostics)
+}
+
+fn parse_documents(layers: &[Layer]) -> Vec<Spanned<DeTable<'_>>> {
+    layers
+        .iter()
+        .filter_map(|l| DeTable::parse(&l.content).ok())
+        .collect()
+}
+
+// merge the layers key by key, the tables being merged recursively
+fn merge(layers: &[Layer]) -> Result<(UserConfig, Table), Vec<Diagnostic>> {
+    let mut merged = Table::new();
+    let mut diagnostics = vec![];
+    for (i, layer) in layers.iter().enumerate() {
+        match layer.content.parse::<Table>() {
+            Ok(table) => merge_table(&mut merged, table),
+            Err(e) => diagnostics.push(Diagnostic {
+                message: e.message().trim_end().to_string(),
+                layer: Some(i),
+                span: e.span(),
+            }),
+        }
+    }
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+    let config = merged.clone().try_into().map_err(|e: toml::de::Error| {
+        let message = suggest(e.message());
+        // the merged table has no span, look for the unknown key in the
+        // layers
+        let key = unknown_field(&message);
+        let documents = parse_documents(layers);
+        let location = key.and_then(|key| {
+            documents
+                .iter()
+                .enumerate()
+                .rev()
+                .find_map(|(i, d)| find_key(d.get_ref(), key).map(|span| (i, span)))
+        });
+        vec![Diagnostic {
+            message,
+            layer: location.as_ref().map(|(i, _)| *i),
+            span: location.map(|(_, span)| span),
+        }]
+    })?;
+    Ok((config, merged))
+}
+
+/// Merge a table over another, key by key, the sub-tables being merged
+/// recursively
+pub fn merge_table(base: &mut Table, layer: Table) {
+    for (key, value) in layer {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base)), Value::Table(table)) => merge_table(base, table),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+// the key named by an unknown field error
+fn unknown_field(message: &str) -> Option<&str> {
+    message
+        .strip_prefix("unknown field `")
+        .and_then(|m| m.split('`').next())
+}
+
+// the span of a key in the `[[profile]]` of the given name, or of its name
+// when the profile does not set the key
+fn find_profile_key(table: &DeTable, name: &str, key: Option<&str>) -> Option<Range<usize>> {
+    let (_, profiles) = table.iter().find(|(k, _)| k.get_ref() == "profile")?;
+    let DeValue::Array(profiles) = profiles.get_ref() else {
+        return None;
+    };
+    profiles.iter().find_map(|profile| {
+        let DeValue::Table(profile) = profile.get_ref() else {
+            return None;
+        };
+        let (name_key, value) = profile.iter().find(|(k, _)| k.get_ref() == "name")?;
+        if value.get_ref().as_str() != Some(name) {
+            return None;
+        }
+        key.and_then(|key| find_key(profile, key))
+            .or_else(|| Some(name_key.span()))
+    })
+}
+
+// the span of a key, at any depth
+fn find_key(table: &DeTable, key: &str) -> Option<Range<usize>> {
+    table.iter().find_map(|(k, v)| {
+        if k.get_ref() == key {
+            return Some(k.span());
+        }
+        match v.get_ref() {
+            DeValue::Table(t) => find_key(t, key),
+            DeValue::Array(a) => a.iter().find_map(|v| match v.get_ref() {
+                DeValue::Table(t) => find_key(t, key),
+                _ => None,
+            }),
+            _ => None,
+        }
+    })
+}
+
+/// Check the config files and print the problems found, returns whether
+/// the config is valid
+pub fn check_config(path: Option<PathBuf>) -> Result<bool> {
+    let files = match path {
+        Some(path) => vec![path],
+        None => config::find_files(),
+    };
+    if files.is_empty() {
+        println!("no config file, the built-in config is used");
+        return Ok(true);
+    }
+    let layers = files
+        .iter()
+        .map(|f| Layer::read(f))
+        .collect::<Result<Vec<Layer>>>()?;
+    match check(&layers) {
+        Ok(_) => {
+            for layer in &layers {
+                println!("{}: ok", layer.path.display());
+            }
+            Ok(true)
+        }
+        Err(diagnostics) => {
+            eprintln!("{}", render(&diagnostics, &layers));
+            Ok(false)
+        }
+    }
+}
+
+// add a "did you mean" to the unknown field and variant errors, their
+// message is `unknown field `name`, expected one of `a`, `b``
+pub fn suggest(message: &str) -> String {
+    let message = message.trim_end();
+    if !message.starts_with("unknown field") && !message.starts_with("unknown variant") {
+        return message.to_string();
+    }
+    let mut names = message.split('`').skip(1).step_by(2);
+    let Some(unknown) = names.next() else {
+        return message.to_string();
+    };
+    let suggestion = names
+        .map(|name| (util::levenshtein(unknown, name), name))
+        .filter(|(distance, _)| *distance <= unknown.chars().count().div_ceil(3))
+        .min_by_key(|(distance, _)| *distance);
+    match suggestion {
+        // the suggestion is enough, drop the list of expected names
+        Some((_, name)) => {
+            let message = message.split(", expected").next().unwrap_or(message);
+            format!("{message}\ndid you mean `{name}`?")
+        }
+        None => message.to_string(),
+    }
+}
+
+/// Render the diagnostics with the line they point to
+///
+/// ```text
+/// error: unknown field `urgencey`
+///  --> bato.toml:12:1
+///    |
+/// 12 | urgencey = "critical"
+///    | ^^^^^^^^
+/// ```
+pub fn render(diagnostics: &[Diagnostic], layers: &[Layer]) -> String {
+    let mut output = String::new();
+    for diagnostic in diagnostics {
+        let layer = diagnostic.layer.and_then(|i| layers.get(i));
+        let path = match layer {
+            Some(layer) => layer.path.display().to_string(),
+            None => layers
+                .iter()
+                .map(|l| l.path.display().to_string())
+                .collect::<Vec<String>>()
+                .join(", "),
+        };
+        let (first, rest) = diagnostic
+            .message
+            .split_once('\n')
+            .unwrap_or((&diagnostic.message, ""));
+        writeln!(output, "error: {first}").ok();
+        let (Some(layer), Some(span)) = (layer, diagnostic.span.clone()) else {
+            writeln!(output, " --> {path}").ok();
+            for note in rest.lines() {
+                writeln!(output, "  = {note}").ok();
+            }
+            continue;
+        };
+        let content = &layer.content;
+        let start = span.start.min(content.len());
+        let line_start = content[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = content[start..]
+            .find('\n')
+            .map_or(content.len(), |i| start + i);
+        let line = &content[line_start..line_end];
+        let line_number = content[..start].matches('\n').count() + 1;
+        let column = content[line_start..start].chars().count() + 1;
+        let width = content[start..span.end.clamp(start, line_end)]
+            .chars()
+            .count()
+            .max(1);
+        let margin = " ".repeat(line_number.to_string().len());
+        writeln!(output, "{margin}--> {path}:{line_number}:{column}").ok();
+        writeln!(output, "{margin} |").ok();
+        writeln!(output, "{line_number} | {line}").ok();
+        writeln!(
+            output,
+            "{margin} | {}{}",
+            " ".repeat(column - 1),
+            "^".repeat(width)
+        )
+        .ok();
+        for note in rest.lines() {
+            writeln!(output, "{margin} = {note}").ok();
+        }
+    }
+    output.trim_end().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(content: &str) -> Layer {
+        Layer {
+            path: PathBuf::from("bato.toml"),
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn suggest_close_names() {
+        assert_eq!(
+            suggest("unknown field `low_levle`, expected one of `low_level`, `critical_level`"),
+            "unknown field `low_levle`\ndid you mean `low_level`?"
+        );
+        // too far from any name, the list is kept
+        let message = "unknown field `xyz`, expected one of `low_level`, `snooze`";
+        assert_eq!(suggest(message), message);
+        let message = "invalid type: string \"a\", expected u32";
+        assert_eq!(suggest(message), message);
+    }
+
+    #[test]
+    fn merge_tables_key_by_key() {
+        let mut base: Table = "low_level = 20\nsnooze = 15\n[low]\nsummary = \"a\"\nbody = \"b\""
+            .parse()
+            .unwrap();
+        let layer: Table = "low_level = 30\n[low]\nbody = \"c\"\nbackends = [\"wall\"]"
+            .parse()
+            .unwrap();
+        merge_table(&mut base, layer);
+        let expected: Table = "low_level = 30\nsnooze = 15\n[low]\nsummary = \"a\"\nbody = \"c\"\nbackends = [\"wall\"]"
+            .parse()
+            .unwrap();
+        assert_eq!(base, expected);
+        // an array is replaced, not merged
+        let layer: Table = "[low]\nbackends = [\"stdout\"]".parse().unwrap();
+        merge_table(&mut base, layer);
+        assert_eq!(base["low"]["backends"].as_array().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn render_points_at_the_key() {
+        let layers = [layer("tick_rate = 5\n[low]\n  urgencey = \"critical\"\n")];
+        let start = layers[0].content.find("urgencey").unwrap();
+        let diagnostic = Diagnostic {
+            message: "unknown field `urgencey`\ndid you mean `urgency`?".to_string(),
+            layer: Some(0),
+            span: Some(start..start + "urgencey".len()),
+        };
+        assert_eq!(
+            render(&[diagnostic], &layers),
+            "error: unknown field `urgencey`
+ --> bato.toml:3:3
+  |
+3 |   urgencey = \"critical\"
+  |   ^^^^^^^^
+  = did you mean `urgency`?"
+        );
+    }
+
+    #[test]
+    fn render_counts_characters() {
+        let layers = [layer("low = { body = \"é\", clé = 1 }\n")];
+        let start = layers[0].content.find("clé").unwrap();
+        let diagnostic = Diagnostic {
+            message: "bad".to_string(),
+            layer: Some(0),
+            span: Some(start..start + "clé".len()),
+        };
+        let output = render(&[diagnostic], &layers);
+        assert!(output.contains("bato.toml:1:21"), "{output}");
+        assert!(
+            output.ends_with(&format!("| {}^^^", " ".repeat(20))),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn render_without_span() {
+        let layers = [layer("")];
+        let diagnostic = Diagnostic {
+            message: "invalid".to_string(),
+            layer: None,
+            span: None,
+        };
+        assert_eq!(
+            render(&[diagnostic], &layers),
+            "error: invalid\n --> bato.toml"
+        );
+    }
+
+    #[test]
+    fn locate_profile_errors() {
+        let layers = [layer(
+            "low_level = 20\n\n[[profile]]\nname = \"travel\"\nlow_levle = 40\n",
+        )];
+        let diagnostics = check(&layers).unwrap_err();
+        assert_eq!(diagnostics.len(), 1);
+        let span = diagnostics[0].span.clone().unwrap();
+        assert_eq!(diagnostics[0].layer, Some(0));
+        assert_eq!(&layers[0].content[span], "low_levle");
+    }
+}
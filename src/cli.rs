@@ -4,9 +4,13 @@
 
 use std::path::PathBuf;
 
-use clap::{Parser, ValueEnum};
+use clap::builder::BoolishValueParser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use serde::Deserialize;
 
+use crate::bar::BarFormat;
+use crate::config::{Backend, IconMode, QuietMode};
+
 #[derive(Parser, Deserialize, Debug, Clone, PartialEq, Eq, ValueEnum, strum::Display)]
 #[serde(rename_all = "lowercase")]
 #[strum(serialize_all = "lowercase")]
@@ -27,6 +31,18 @@ pub enum Logs {
     File,
 }
 
+/// The notifications `test-notify` sends
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyTarget {
+    Charging,
+    Discharging,
+    Full,
+    Low,
+    Critical,
+    All,
+}
+
 #[derive(Parser, Deserialize, Debug, Clone)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
@@ -42,4 +58,174 @@ pub struct Cli {
     /// Use a custom config file
     #[arg(short = 'c', long, value_name = "FILE")]
     pub config: Option<PathBuf>,
+
+    #[command(flatten)]
+    pub overrides: Overrides,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Config options set by the environment or the command line, over the
+/// config file. A `--no-*` flag wins over its positive form
+#[derive(Args, Deserialize, Debug, Clone, Default)]
+#[command(next_help_heading = "Config overrides")]
+pub struct Overrides {
+    /// The tick rate, in second
+    #[arg(long, env = "BATO_TICK_RATE", value_name = "SECONDS")]
+    pub tick_rate: Option<u32>,
+
+    /// The battery to monitor
+    #[arg(long, env = "BATO_BAT_NAME", value_name = "NAME")]
+    pub battery: Option<String>,
+
+    /// The low level of the battery, as a percentage
+    #[arg(long, env = "BATO_LOW_LEVEL", value_name = "PERCENT")]
+    pub low_level: Option<u32>,
+
+    /// The critical level of the battery, as a percentage
+    #[arg(long, env = "BATO_CRITICAL_LEVEL", value_name = "PERCENT")]
+    pub critical_level: Option<u32>,
+
+    /// Calculate the level based on the full design value
+    #[arg(long, env = "BATO_FULL_DESIGN", value_name = "BOOL")]
+    #[arg(num_args = 0..=1, default_missing_value = "true")]
+    #[arg(value_parser = BoolishValueParser::new())]
+    pub full_design: Option<bool>,
+    /// Disable `--full-design`
+    #[arg(long)]
+    pub no_full_design: bool,
+
+    /// Poll fast near a threshold, slowly when charging or full
+    #[arg(long, env = "BATO_ADAPTIVE_TICK", value_name = "BOOL")]
+    #[arg(num_args = 0..=1, default_missing_value = "true")]
+    #[arg(value_parser = BoolishValueParser::new())]
+    pub adaptive_tick: Option<bool>,
+    /// Disable `--adaptive-tick`
+    #[arg(long)]
+    pub no_adaptive_tick: bool,
+
+    /// The lower bound of the adaptive tick rate, in second
+    #[arg(long, env = "BATO_MIN_TICK_RATE", value_name = "SECONDS")]
+    pub min_tick_rate: Option<u32>,
+
+    /// The upper bound of the adaptive tick rate, in second
+    #[arg(long, env = "BATO_MAX_TICK_RATE", value_name = "SECONDS")]
+    pub max_tick_rate: Option<u32>,
+
+    /// Debounce window of AC events, in millisecond
+    #[arg(long, env = "BATO_AC_DEBOUNCE", value_name = "MILLISECONDS")]
+    pub ac_debounce: Option<u32>,
+
+    /// Reload the config when a config file changes
+    #[arg(long, env = "BATO_WATCH_CONFIG", value_name = "BOOL")]
+    #[arg(num_args = 0..=1, default_missing_value = "true")]
+    #[arg(value_parser = BoolishValueParser::new())]
+    pub watch_config: Option<bool>,
+    /// Disable `--watch-config`
+    #[arg(long)]
+    pub no_watch_config: bool,
+
+    /// Where notifications are sent, comma separated
+    #[arg(long, env = "BATO_BACKENDS", value_delimiter = ',')]
+    pub backends: Option<Vec<Backend>>,
+
+    /// Replace the previous notification in place
+    #[arg(long, env = "BATO_REPLACE", value_name = "BOOL")]
+    #[arg(num_args = 0..=1, default_missing_value = "true")]
+    #[arg(value_parser = BoolishValueParser::new())]
+    pub replace: Option<bool>,
+    /// Disable `--replace`
+    #[arg(long)]
+    pub no_replace: bool,
+
+    /// Snooze duration, in minute
+    #[arg(long, env = "BATO_SNOOZE", value_name = "MINUTES")]
+    pub snooze: Option<u32>,
+
+    /// How the notification icon is picked
+    #[arg(long, env = "BATO_ICON_MODE")]
+    pub icon_mode: Option<IconMode>,
+
+    /// The level step of the icons in `level` icon mode
+    #[arg(long, env = "BATO_ICON_STEP", value_name = "PERCENT")]
+    pub icon_step: Option<u32>,
+
+    /// Attempts before giving up on a notification, 0 disables the retry
+    #[arg(long, env = "BATO_RETRY_MAX_ATTEMPTS", value_name = "COUNT")]
+    pub retry_max_attempts: Option<u32>,
+
+    /// Delay before the first retry, in second, doubled after each attempt
+    #[arg(long, env = "BATO_RETRY_INTERVAL", value_name = "SECONDS")]
+    pub retry_interval: Option<u32>,
+
+    /// What happens to the notifications during the quiet hours
+    #[arg(long, env = "BATO_QUIET_MODE")]
+    pub quiet_mode: Option<QuietMode>,
+
+    /// The URL the webhook backend POSTs to
+    #[arg(long, env = "BATO_WEBHOOK_URL", value_name = "URL")]
+    pub webhook_url: Option<String>,
+
+    /// Timeout of the webhook requests, in second
+    #[arg(long, env = "BATO_WEBHOOK_TIMEOUT", value_name = "SECONDS")]
+    pub webhook_timeout: Option<u32>,
+}
+
+#[derive(Subcommand, Deserialize, Debug, Clone)]
+pub enum Command {
+    /// Show the notification server information and capabilities
+    ServerInfo,
+    /// Check a config file, the default one if none is given
+    CheckConfig {
+        #[arg(value_name = "FILE")]
+        file: Option<PathBuf>,
+    },
+    /// Print the reference config, every option set to its default value
+    PrintDefaultConfig,
+    /// Print the battery level, status, power, health and the state bato
+    /// would be in. Exits with 2 when the battery is critical
+    Status {
+        /// Print as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the battery status for a status bar, on every change. Follows
+    /// the running bato, or reads the battery itself
+    Bar {
+        /// waybar, i3bar or custom=TEMPLATE, eg. custom="{icon} {level}%"
+        #[arg(long, default_value = "waybar")]
+        format: BarFormat,
+    },
+    /// List the power supplies with their attributes, and the battery bato
+    /// uses
+    List {
+        /// Print the raw uevent file too
+        #[arg(long, short)]
+        verbose: bool,
+    },
+    /// Send the notification of a state, to preview its text, icon and
+    /// urgency. Rendered with the current battery values, or fake ones
+    TestNotify {
+        #[arg(value_enum)]
+        target: NotifyTarget,
+    },
+    /// Detect the batteries and write a config, asking for the main
+    /// settings
+    Init,
+    /// Read or edit the config
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+}
+
+#[derive(Subcommand, Deserialize, Debug, Clone)]
+pub enum ConfigCommand {
+    /// Print the effective value of an option, eg. `low_level` or
+    /// `low.urgency`. Exits with 1 when there is no such option
+    Get { key: String },
+    /// Set an option in the config file, keeping its comments, and reload
+    /// the running bato
+    Set { key: String, value: String },
 }
@@ -3,41 +3,253 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use anyhow::anyhow;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::{env, fs};
-use tracing::{error, info, instrument};
+use toml::Table;
+use tracing::{debug, error, info, instrument, warn};
 
-use crate::{APP_DIR, CONFIG_FILE, XDG_CONFIG_HOME, util};
+use crate::check::{self, Layer};
+use crate::cli::Overrides;
+use crate::util::LocalTime;
+use crate::{APP_DIR, CONFIG_FILE, XDG_CONFIG_HOME, default_config, trace};
 
-const DEFAULT_TICK_RATE: u32 = 30;
-const DEFAULT_LOW_LEVEL: u32 = 20;
-const DEFAULT_CRITICAL_LEVEL: u32 = 5;
-const DEFAULT_FULL_DESIGN: bool = true;
+const XDG_CONFIG_DIRS: &str = "XDG_CONFIG_DIRS";
+const DEFAULT_CONFIG_DIRS: &str = "/etc/xdg";
+pub const DROP_IN_DIR: &str = "conf.d";
 
-#[derive(Debug, Deserialize, Clone, Copy)]
+pub const DEFAULT_TICK_RATE: u32 = 30;
+pub const DEFAULT_LOW_LEVEL: u32 = 20;
+pub const DEFAULT_CRITICAL_LEVEL: u32 = 5;
+pub const DEFAULT_FULL_DESIGN: bool = true;
+pub const DEFAULT_ADAPTIVE_TICK: bool = false;
+pub const DEFAULT_MIN_TICK_RATE: u32 = 5;
+pub const DEFAULT_MAX_TICK_RATE: u32 = 120;
+pub const DEFAULT_AC_DEBOUNCE: u32 = 0;
+pub const DEFAULT_WATCH_CONFIG: bool = false;
+pub const DEFAULT_ICON_STEP: u32 = 10;
+pub const DEFAULT_REPLACE: bool = true;
+pub const DEFAULT_SNOOZE: u32 = 15;
+pub const DEFAULT_RETRY_MAX_ATTEMPTS: u32 = 5;
+pub const DEFAULT_RETRY_INTERVAL: u32 = 5;
+pub const DEFAULT_WEBHOOK_TIMEOUT: u32 = 10;
+pub const DEFAULT_BACKENDS: [Backend; 1] = [Backend::Desktop];
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, strum::AsRefStr)]
 #[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
 pub enum Urgency {
     Low,
     Normal,
     Critical,
 }
 
+#[derive(
+    Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq, strum::AsRefStr, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum IconMode {
+    #[default]
+    Fixed,
+    Level,
+    Map,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, strum::AsRefStr)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Action {
+    Snooze,
+    Suspend,
+    Hibernate,
+    Dismiss,
+}
+
+#[derive(
+    Debug, Deserialize, Clone, Copy, PartialEq, Eq, Hash, strum::AsRefStr, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum Backend {
+    Desktop,
+    Stdout,
+    Exec,
+    Wall,
+    Webhook,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq, strum::AsRefStr)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct StdoutBackend {
+    #[serde(default)]
+    pub format: OutputFormat,
+}
+
 #[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ExecBackend {
+    pub command: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WebhookBackend {
+    pub url: String,
+    /// In second
+    pub timeout: Option<u32>,
+}
+
+/// Settings of the backends
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Backends {
+    pub stdout: Option<StdoutBackend>,
+    pub exec: Option<ExecBackend>,
+    pub webhook: Option<WebhookBackend>,
+}
+
+/// Retry of the notifications that failed to be sent
+#[derive(Debug, Deserialize, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct Retry {
+    pub max_attempts: u32,
+    /// In second, doubled after each attempt
+    pub interval: u32,
+    /// Backends used once all attempts failed
+    pub fallback: Vec<Backend>,
+}
+
+/// Icon used from the `from` level up to the next entry of the icon map
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct IconMapEntry {
+    pub from: u32,
+    pub icon: String,
+    pub charging_icon: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct Notification {
     pub summary: String,
     pub body: Option<String>,
     pub icon: Option<String>,
     pub urgency: Option<Urgency>,
+    pub min_interval: Option<u32>,
+    pub close_on_exit: Option<bool>,
+    pub actions: Option<Vec<Action>>,
+    pub value: Option<bool>,
+    pub category: Option<String>,
+    pub stack_tag: Option<String>,
+    pub synchronous: Option<String>,
+    pub expire_timeout: Option<u32>,
+    pub transient: Option<bool>,
+    pub resident: Option<bool>,
+    pub sound_name: Option<String>,
+    pub sound_file: Option<String>,
+    pub app_name: Option<String>,
+    pub desktop_entry: Option<String>,
+    pub backends: Option<Vec<Backend>>,
+}
+
+#[derive(
+    Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq, strum::AsRefStr, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum QuietMode {
+    Suppress,
+    #[default]
+    Defer,
+}
+
+// same order as `tm_wday`
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Weekday {
+    Sun,
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+}
+
+/// A time of the day `HH:MM`, stored in minutes since midnight
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "String")]
+pub struct DayTime(pub u32);
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct TimeRange {
+    pub from: DayTime,
+    pub to: DayTime,
+    pub days: Option<Vec<Weekday>>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct QuietHours {
+    #[serde(default)]
+    pub mode: QuietMode,
+    pub ranges: Vec<TimeRange>,
+}
+
+/// Config options applied over the config while its conditions hold
+#[derive(Debug, Deserialize, Clone)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub when: Condition,
+    #[serde(flatten)]
+    pub settings: Table,
+}
+
+/// All the given conditions must hold
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Condition {
+    pub ac: Option<bool>,
+    pub time: Option<Vec<TimeRange>>,
+    /// A power supply, eg. a dock or a charger, present and online
+    pub device: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct UserConfig {
     pub tick_rate: Option<u32>,
     pub bat_name: Option<String>,
     pub low_level: Option<u32>,
     pub critical_level: Option<u32>,
     pub full_design: Option<bool>,
+    pub adaptive_tick: Option<bool>,
+    pub min_tick_rate: Option<u32>,
+    pub max_tick_rate: Option<u32>,
+    pub ac_debounce: Option<u32>,
+    pub watch_config: Option<bool>,
+    pub backends: Option<Vec<Backend>>,
+    pub backend: Option<Backends>,
+    pub retry: Option<Retry>,
+    pub replace: Option<bool>,
+    pub snooze: Option<u32>,
+    pub icon_mode: Option<IconMode>,
+    pub icon_step: Option<u32>,
+    pub icon_map: Option<Vec<IconMapEntry>>,
+    pub quiet_hours: Option<QuietHours>,
+    pub profile: Option<Vec<Profile>>,
     pub critical: Option<Notification>,
     pub low: Option<Notification>,
     pub full: Option<Notification>,
@@ -47,11 +259,35 @@ pub struct UserConfig {
 
 #[derive(Debug, Clone)]
 pub struct Config {
+    /// The config file given on the command line, overriding the lookup
+    pub path: Option<PathBuf>,
+    /// Options set by the environment and the command line
+    pub overrides: Overrides,
+    /// The files the config was read from, from the lowest to the
+    /// highest priority
+    pub files: Vec<PathBuf>,
     pub tick_rate: u32,
     pub bat_name: Option<String>,
     pub low_level: u32,
     pub critical_level: u32,
     pub full_design: bool,
+    pub adaptive_tick: bool,
+    pub min_tick_rate: u32,
+    pub max_tick_rate: u32,
+    pub ac_debounce: u32,
+    pub watch_config: bool,
+    pub backends: Vec<Backend>,
+    pub backend: Backends,
+    pub retry: Retry,
+    pub replace: bool,
+    pub snooze: u32,
+    pub icon_mode: IconMode,
+    pub icon_step: u32,
+    pub icon_map: Vec<IconMapEntry>,
+    pub quiet_hours: Option<QuietHours>,
+    pub profiles: Vec<Profile>,
+    /// The config as read, before the profiles are applied
+    pub base: Table,
     pub critical: Option<Notification>,
     pub low: Option<Notification>,
     pub full: Option<Notification>,
@@ -60,49 +296,289 @@ pub struct Config {
 }
 
 impl Config {
+    /// The user config file, `bato print-default-config` output goes there
+    pub fn default_file() -> anyhow::Result<PathBuf> {
+        Ok(user_dir()?.join(CONFIG_FILE))
+    }
+
+    /// The built-in config, used when there is no config file
+    pub fn builtin() -> anyhow::Result<Self> {
+        let layer = Layer {
+            path: PathBuf::from("<built-in>"),
+            content: default_config::reference(),
+        };
+        check::check(&[layer]).map_err(|_| anyhow!("invalid built-in config"))
+    }
+
+    /// Load the given config file, or the system and user config files
+    /// layered over each other, then apply the overrides
     #[instrument]
-    pub fn new(path: Option<PathBuf>) -> anyhow::Result<Self> {
-        let config_file = path.unwrap_or({
-            let home = env::var("HOME")?;
-            let mut config_dir = env::var(XDG_CONFIG_HOME)
-                .map(PathBuf::from)
-                .unwrap_or_else(|_| Path::new(&home).join(".config"));
-            config_dir.push(APP_DIR);
-            util::check_dir_or_create(&config_dir)?;
-
-            config_dir.join(CONFIG_FILE)
-        });
-        info!("config file: {:?}", config_file);
-        let content = fs::read_to_string(&config_file).map_err(|e| {
-            let error = format!(
-                "failed to read config file {}: {}",
-                config_file.display(),
-                e
-            );
-            error!(error);
-            anyhow!(error)
-        })?;
-        let config: UserConfig = toml::from_str(&content).map_err(|e| {
-            let error = format!(
-                "failed to parse config file {}: {}",
-                config_file.display(),
-                e
-            );
+    pub fn new(path: Option<PathBuf>, overrides: Overrides) -> anyhow::Result<Self> {
+        let files = match &path {
+            Some(path) => vec![path.clone()],
+            None => find_files(),
+        };
+        if files.is_empty() {
+            let config = Config {
+                path,
+                ..Self::builtin()?
+            }
+            .with_overrides(overrides)?;
+            info!("no config file, using the built-in config");
+            return Ok(config);
+        }
+        let layers = files
+            .iter()
+            .map(|f| Layer::read(f).inspect_err(|e| error!("{e}")))
+            .collect::<anyhow::Result<Vec<Layer>>>()?;
+        let config = check::check(&layers).map_err(|diagnostics| {
+            let error = format!("invalid config\n{}", check::render(&diagnostics, &layers));
             error!(error);
             anyhow!(error)
         })?;
-        Ok(config.into())
+        let config = Config {
+            path,
+            files,
+            ..config
+        }
+        .with_overrides(overrides)?;
+        info!("config files: {:?}", config.files);
+        Ok(config)
+    }
+
+    /// The config with the given profiles applied over it, in order
+    #[instrument(skip(self))]
+    pub fn profiled(&self, names: &[String]) -> anyhow::Result<Self> {
+        let mut table = self.base.clone();
+        for profile in self.profiles.iter().filter(|p| names.contains(&p.name)) {
+            let mut settings = profile.settings.clone();
+            settings.remove("profile");
+            check::merge_table(&mut table, settings);
+        }
+        let config: UserConfig = table
+            .try_into()
+            .map_err(|e: toml::de::Error| anyhow!(e.message().trim_end().to_string()))?;
+        Config {
+            path: self.path.clone(),
+            files: self.files.clone(),
+            base: self.base.clone(),
+            ..Config::from(config)
+        }
+        .with_overrides(self.overrides.clone())
+    }
+
+    // apply the overrides, the result is validated again
+    fn with_overrides(mut self, overrides: Overrides) -> anyhow::Result<Self> {
+        let o = &overrides;
+        let flag = |value: Option<bool>, no: bool| if no { Some(false) } else { value };
+        self.tick_rate = o.tick_rate.unwrap_or(self.tick_rate);
+        self.bat_name = o.battery.clone().or(self.bat_name);
+        self.low_level = o.low_level.unwrap_or(self.low_level);
+        self.critical_level = o.critical_level.unwrap_or(self.critical_level);
+        self.full_design = flag(o.full_design, o.no_full_design).unwrap_or(self.full_design);
+        self.adaptive_tick =
+            flag(o.adaptive_tick, o.no_adaptive_tick).unwrap_or(self.adaptive_tick);
+        self.min_tick_rate = o.min_tick_rate.unwrap_or(self.min_tick_rate);
+        self.max_tick_rate = o.max_tick_rate.unwrap_or(self.max_tick_rate);
+        self.ac_debounce = o.ac_debounce.unwrap_or(self.ac_debounce);
+        self.watch_config = flag(o.watch_config, o.no_watch_config).unwrap_or(self.watch_config);
+        self.backends = o.backends.clone().unwrap_or(self.backends);
+        self.replace = flag(o.replace, o.no_replace).unwrap_or(self.replace);
+        self.snooze = o.snooze.unwrap_or(self.snooze);
+        self.icon_mode = o.icon_mode.unwrap_or(self.icon_mode);
+        self.icon_step = o.icon_step.unwrap_or(self.icon_step);
+        self.retry.max_attempts = o.retry_max_attempts.unwrap_or(self.retry.max_attempts);
+        self.retry.interval = o.retry_interval.unwrap_or(self.retry.interval);
+        // without time ranges, there are no quiet hours to set the mode of
+        if let (Some(mode), Some(quiet_hours)) = (o.quiet_mode, self.quiet_hours.as_mut()) {
+            quiet_hours.mode = mode;
+        }
+        if let Some(url) = &o.webhook_url {
+            let webhook = self.backend.webhook.get_or_insert(WebhookBackend {
+                url: url.clone(),
+                timeout: None,
+            });
+            webhook.url = url.clone();
+        }
+        if let Some(timeout) = o.webhook_timeout {
+            match self.backend.webhook.as_mut() {
+                Some(webhook) => webhook.timeout = Some(timeout),
+                None => warn!("no webhook url, --webhook-timeout ignored"),
+            }
+        }
+        self.overrides = overrides;
+        self.validate()?;
+        // before logging, stdout is where the notifications go
+        if self.uses_backend(Backend::Stdout) {
+            trace::logs_to_stderr();
+        }
+        Ok(self)
+    }
+
+    /// Whether a backend may be used, by the defaults, a notification, the
+    /// retry or a profile
+    pub fn uses_backend(&self, backend: Backend) -> bool {
+        self.backends.contains(&backend) || names_backend(&self.base, backend.as_ref())
+    }
+
+    /// The paths to watch for config changes: the files, and the drop-in
+    /// directories
+    pub fn watched(&self) -> Vec<PathBuf> {
+        if let Some(path) = &self.path {
+            return vec![path.clone()];
+        }
+        config_dirs()
+            .into_iter()
+            .filter(|dir| dir.is_dir())
+            .flat_map(|dir| {
+                let drop_ins = dir.join(DROP_IN_DIR);
+                let mut paths = vec![dir.join(CONFIG_FILE)];
+                if drop_ins.is_dir() {
+                    paths.push(drop_ins);
+                }
+                paths
+            })
+            .collect()
+    }
+
+    /// Check the values that parse but make no sense together
+    #[instrument(skip_all)]
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let problems = self.problems();
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let problems: Vec<String> = problems.into_iter().map(|(_, p)| p).collect();
+        let error = format!("invalid config: {}", problems.join(", "));
+        error!(error);
+        Err(anyhow!(error))
+    }
+
+    /// The values that make no sense, with the key to blame
+    pub fn problems(&self) -> Vec<(&'static str, String)> {
+        let mut problems = vec![];
+        if self.tick_rate == 0 {
+            problems.push(("tick_rate", "tick_rate must be greater than 0".to_string()));
+        }
+        for (key, level) in [
+            ("low_level", self.low_level),
+            ("critical_level", self.critical_level),
+        ] {
+            if level > 100 {
+                problems.push((key, format!("{key} must be at most 100, got {level}")));
+            }
+        }
+        if self.critical_level >= self.low_level {
+            problems.push((
+                "critical_level",
+                format!(
+                    "critical_level ({}) must be lower than low_level ({})",
+                    self.critical_level, self.low_level
+                ),
+            ));
+        }
+        if self.adaptive_tick && self.min_tick_rate == 0 {
+            problems.push((
+                "min_tick_rate",
+                "min_tick_rate must be greater than 0".to_string(),
+            ));
+        }
+        if self.min_tick_rate > self.max_tick_rate {
+            problems.push((
+                "min_tick_rate",
+                format!(
+                    "min_tick_rate ({}) must not exceed max_tick_rate ({})",
+                    self.min_tick_rate, self.max_tick_rate
+                ),
+            ));
+        }
+        problems
     }
 }
 
+// the user config directory
+fn user_dir() -> anyhow::Result<PathBuf> {
+    let home = env::var("HOME")?;
+    let config_dir = env::var(XDG_CONFIG_HOME)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| Path::new(&home).join(".config"));
+    Ok(config_dir.join(APP_DIR))
+}
+
+// the config directories, from the lowest to the highest priority: the
+// system ones from `$XDG_CONFIG_DIRS` (most important first), then the
+// user one
+fn config_dirs() -> Vec<PathBuf> {
+    let system = env::var(XDG_CONFIG_DIRS)
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or(DEFAULT_CONFIG_DIRS.to_string());
+    let mut dirs: Vec<PathBuf> = system
+        .split(':')
+        .filter(|dir| !dir.is_empty())
+        .rev()
+        .map(|dir| Path::new(dir).join(APP_DIR))
+        .collect();
+    match user_dir() {
+        Ok(dir) => dirs.push(dir),
+        Err(e) => warn!("no user config directory: {e}"),
+    }
+    dirs
+}
+
+/// The config files found, from the lowest to the highest priority. In
+/// each config directory, `bato.toml` then the `conf.d/*.toml` drop-ins
+/// in lexical order
+#[instrument]
+pub fn find_files() -> Vec<PathBuf> {
+    let mut files = vec![];
+    for dir in config_dirs() {
+        let file = dir.join(CONFIG_FILE);
+        if file.is_file() {
+            files.push(file);
+        }
+        let Ok(entries) = fs::read_dir(dir.join(DROP_IN_DIR)) else {
+            continue;
+        };
+        let mut drop_ins: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|e| e == "toml"))
+            .collect();
+        drop_ins.sort();
+        files.extend(drop_ins);
+    }
+    debug!("config files found: {files:?}");
+    files
+}
+
 impl From<UserConfig> for Config {
     fn from(config: UserConfig) -> Self {
         Config {
+            path: None,
+            overrides: Overrides::default(),
+            files: vec![],
             tick_rate: config.tick_rate.unwrap_or(DEFAULT_TICK_RATE),
             bat_name: config.bat_name,
             low_level: config.low_level.unwrap_or(DEFAULT_LOW_LEVEL),
             critical_level: config.critical_level.unwrap_or(DEFAULT_CRITICAL_LEVEL),
             full_design: config.full_design.unwrap_or(DEFAULT_FULL_DESIGN),
+            adaptive_tick: config.adaptive_tick.unwrap_or(DEFAULT_ADAPTIVE_TICK),
+            min_tick_rate: config.min_tick_rate.unwrap_or(DEFAULT_MIN_TICK_RATE),
+            max_tick_rate: config.max_tick_rate.unwrap_or(DEFAULT_MAX_TICK_RATE),
+            ac_debounce: config.ac_debounce.unwrap_or(DEFAULT_AC_DEBOUNCE),
+            watch_config: config.watch_config.unwrap_or(DEFAULT_WATCH_CONFIG),
+            backends: config.backends.unwrap_or(DEFAULT_BACKENDS.to_vec()),
+            backend: config.backend.unwrap_or_default(),
+            retry: config.retry.unwrap_or_default(),
+            replace: config.replace.unwrap_or(DEFAULT_REPLACE),
+            snooze: config.snooze.unwrap_or(DEFAULT_SNOOZE),
+            icon_mode: config.icon_mode.unwrap_or_default(),
+            icon_step: config.icon_step.unwrap_or(DEFAULT_ICON_STEP),
+            icon_map: config.icon_map.unwrap_or_default(),
+            quiet_hours: config.quiet_hours,
+            profiles: config.profile.unwrap_or_default(),
+            base: Table::new(),
             critical: config.critical,
             low: config.low,
             full: config.full,
@@ -121,3 +597,148 @@ impl From<&Urgency> for notify_rust::Urgency {
         }
     }
 }
+
+impl Default for Retry {
+    fn default() -> Self {
+        Retry {
+            max_attempts: DEFAULT_RETRY_MAX_ATTEMPTS,
+            interval: DEFAULT_RETRY_INTERVAL,
+            fallback: vec![],
+        }
+    }
+}
+
+impl TryFrom<String> for DayTime {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let error = || format!("invalid time `{value}`, expected HH:MM");
+        let (hours, minutes) = value.split_once(':').ok_or_else(error)?;
+        let hours: u32 = hours.parse().map_err(|_| error())?;
+        let minutes: u32 = minutes.parse().map_err(|_| error())?;
+        if hours > 23 || minutes > 59 {
+            return Err(error());
+        }
+        Ok(DayTime(hours * 60 + minutes))
+    }
+}
+
+impl TimeRange {
+    /// Check if the given local time falls in the range. A range ending
+    /// before it starts spans midnight, its days refer to the day it starts.
+    /// A range ending when it starts spans 24 hours
+    pub fn contains(&self, time: &LocalTime) -> bool {
+        let day_matches = |weekday: u32| {
+            self.days
+                .as_ref()
+                .is_none_or(|days| days.iter().any(|d| *d as u32 == weekday))
+        };
+        let now = time.minutes;
+        if self.from < self.to {
+            day_matches(time.weekday) && self.from.0 <= now && now < self.to.0
+        } else if now >= self.from.0 {
+            day_matches(time.weekday)
+        } else {
+            now < self.to.0 && day_matches((time.weekday + 6) % 7)
+        }
+    }
+}
+
+// whether a `backends` or `fallback` array of the table, or of a nested
+// table, holds the backend
+fn names_backend(table: &Table, name: &str) -> bool {
+    table.iter().any(|(key, value)| match value {
+        toml::Value::Array(values) if key == "backends" || key == "fallback" => {
+            values.iter().any(|v| v.as_str() == Some(name))
+        }
+        toml::Value::Array(values) => values
+            .iter()
+            .filter_map(toml::Value::as_table)
+            .any(|t| names_backend(t, name)),
+        toml::Value::Table(table) => names_backend(table, name),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(content: &str) -> TimeRange {
+        toml::from_str(content).unwrap()
+    }
+
+    fn at(weekday: Weekday, hours: u32, minutes: u32) -> LocalTime {
+        LocalTime {
+            weekday: weekday as u32,
+            minutes: hours * 60 + minutes,
+        }
+    }
+
+    #[test]
+    fn time_range_within_a_day() {
+        let range = range(
+            r#"from = "13:00"
+to = "15:00""#,
+        );
+        assert!(range.contains(&at(Weekday::Mon, 13, 0)));
+        assert!(range.contains(&at(Weekday::Mon, 14, 59)));
+        assert!(!range.contains(&at(Weekday::Mon, 15, 0)));
+        assert!(!range.contains(&at(Weekday::Mon, 12, 59)));
+    }
+
+    #[test]
+    fn time_range_across_midnight() {
+        let range = range(
+            r#"from = "22:00"
+to = "07:30"
+days = ["fri"]"#,
+        );
+        assert!(range.contains(&at(Weekday::Fri, 22, 0)));
+        assert!(range.contains(&at(Weekday::Fri, 23, 59)));
+        // the night started on friday
+        assert!(range.contains(&at(Weekday::Sat, 3, 0)));
+        assert!(!range.contains(&at(Weekday::Sat, 7, 30)));
+        assert!(!range.contains(&at(Weekday::Sat, 22, 0)));
+        // the night started on thursday
+        assert!(!range.contains(&at(Weekday::Fri, 3, 0)));
+    }
+
+    #[test]
+    fn time_range_rolls_over_the_week() {
+        let range = range(
+            r#"from = "23:00"
+to = "01:00"
+days = ["sat"]"#,
+        );
+        assert!(range.contains(&at(Weekday::Sun, 0, 30)));
+        assert!(!range.contains(&at(Weekday::Sat, 0, 30)));
+    }
+
+    #[test]
+    fn time_range_of_a_whole_day() {
+        let all_day = range(
+            r#"from = "00:00"
+to = "00:00""#,
+        );
+        assert!(all_day.contains(&at(Weekday::Mon, 0, 0)));
+        assert!(all_day.contains(&at(Weekday::Wed, 12, 0)));
+        assert!(all_day.contains(&at(Weekday::Sun, 23, 59)));
+        let friday = range(
+            r#"from = "08:00"
+to = "08:00"
+days = ["fri"]"#,
+        );
+        assert!(friday.contains(&at(Weekday::Fri, 8, 0)));
+        assert!(friday.contains(&at(Weekday::Sat, 7, 59)));
+        assert!(!friday.contains(&at(Weekday::Sat, 8, 0)));
+        assert!(!friday.contains(&at(Weekday::Fri, 7, 59)));
+    }
+
+    #[test]
+    fn day_time() {
+        assert_eq!(DayTime::try_from("07:30".to_string()).unwrap().0, 450);
+        assert!(DayTime::try_from("24:00".to_string()).is_err());
+        assert!(DayTime::try_from("7h30".to_string()).is_err());
+    }
+}
@@ -0,0 +1,279 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::config::{
+    DEFAULT_AC_DEBOUNCE, DEFAULT_ADAPTIVE_TICK, DEFAULT_BACKENDS, DEFAULT_CRITICAL_LEVEL,
+    DEFAULT_FULL_DESIGN, DEFAULT_ICON_STEP, DEFAULT_LOW_LEVEL, DEFAULT_MAX_TICK_RATE,
+    DEFAULT_MIN_TICK_RATE, DEFAULT_REPLACE, DEFAULT_RETRY_INTERVAL, DEFAULT_RETRY_MAX_ATTEMPTS,
+    DEFAULT_SNOOZE, DEFAULT_TICK_RATE, DEFAULT_WATCH_CONFIG, DEFAULT_WEBHOOK_TIMEOUT, IconMode,
+    OutputFormat, QuietMode,
+};
+
+/// The reference config, every option commented and set to its default
+/// value. Also the built-in config used when there is no config file
+pub fn reference() -> String {
+    render(None)
+}
+
+/// The reference config, monitoring the given battery
+pub fn with_battery(name: &str) -> String {
+    render(Some(name))
+}
+
+fn render(bat_name: Option<&str>) -> String {
+    let bat_name = match bat_name {
+        Some(name) => format!("bat_name = \"{name}\""),
+        None => "# bat_name = \"BAT0\"".to_string(),
+    };
+    let backends = DEFAULT_BACKENDS
+        .iter()
+        .map(|b| format!("\"{}\"", b.as_ref()))
+        .collect::<Vec<String>>()
+        .join(", ");
+    format!(
+        r##"# The tick rate, in second, at which battery info is polled
+# default {tick_rate}
+tick_rate = {tick_rate}
+
+# Adaptive polling: poll fast when close to the low or critical level
+# (or when the estimated time to reach it is short), slowly when
+# charging or full. `tick_rate` is then used only as a fallback
+# default {adaptive_tick}
+adaptive_tick = {adaptive_tick}
+
+# The bounds of the adaptive tick rate, in second
+# default {min_tick_rate} and {max_tick_rate}
+min_tick_rate = {min_tick_rate}
+max_tick_rate = {max_tick_rate}
+
+# The battery to monitor, located in `/sys/class/power_supply/<BAT_NAME>/`
+# If not provided, bato will try to find one
+{bat_name}
+
+# The critical level of the battery, as a percentage
+# default {critical_level}
+critical_level = {critical_level}
+
+# The low level of the battery, as a percentage
+# default {low_level}
+low_level = {low_level}
+
+# Whether the current level is calculated based on the full design value
+# default {full_design}
+full_design = {full_design}
+
+# Debounce window of AC plug/unplug events, in millisecond. Only the
+# state in which the AC settles is notified, useful with flaky chargers
+# default {ac_debounce} (0 disables it)
+ac_debounce = {ac_debounce}
+
+# Reload the config when this file changes. The config is also reloaded
+# on SIGHUP. An invalid config is rejected and the current one is kept.
+# `bat_name`, `full_design` and `watch_config` changes apply on restart
+# default {watch_config}
+watch_config = {watch_config}
+
+# Where notifications are sent, a list of backends
+# `desktop` the desktop notification server, over D-Bus
+# `stdout` printed on stdout, see [backend.stdout]
+# `exec` passed to a command, see [backend.exec]
+# `wall` broadcast to all terminals
+# `webhook` POSTed as JSON, see [backend.webhook]
+# A notification can override it with its own `backends`
+# default [{backends}]
+backends = [{backends}]
+
+# Backends settings
+# [backend.stdout]
+# text | json (default {output_format})
+# format = "json"
+#
+# [backend.exec]
+# The command receives the notification in the environment variables
+# BATO_SUMMARY BATO_BODY BATO_ICON BATO_STATE BATO_LEVEL BATO_URGENCY
+# command = ["/path/to/script", "--some-arg"]
+#
+# [backend.webhook]
+# url = "https://example.com/hook"
+# timeout in second, default {webhook_timeout}
+# timeout = {webhook_timeout}
+
+# Retry of the notifications a backend failed to send, eg. when the
+# notification server is not running yet. Queued desktop notifications
+# are sent as soon as a notification server appears
+# [retry]
+# attempts before giving up, 0 disables the retry, default {max_attempts}
+# max_attempts = {max_attempts}
+# delay before the first retry in second, doubled after each attempt,
+# default {interval}
+# interval = {interval}
+# backends used once all attempts failed, default none
+# fallback = ["wall"]
+
+# Whether a notification replaces the previous one in place, instead
+# of stacking popups
+# default {replace}
+replace = {replace}
+
+# Snooze duration of the `snooze` action, in minute
+# default {snooze}
+snooze = {snooze}
+
+# How the notification icon is picked
+# `fixed` the `icon` of the notification
+# `level` an icon from the current level and charging status, like
+#   `battery-level-40-symbolic` or `battery-level-40-charging-symbolic`,
+#   the level being rounded to `icon_step`
+# `map` the icon of the `icon_map` entry covering the current level,
+#   falling back on the `icon` of the notification
+# default {icon_mode}
+icon_mode = "{icon_mode}"
+# default {icon_step}
+icon_step = {icon_step}
+# icon_map = [
+#   {{ from = 0, icon = "battery-caution", charging_icon = "battery-caution-charging" }},
+#   {{ from = 20, icon = "battery-low", charging_icon = "battery-low-charging" }},
+#   {{ from = 50, icon = "battery-good", charging_icon = "battery-good-charging" }},
+#   {{ from = 90, icon = "battery-full", charging_icon = "battery-full-charging" }},
+# ]
+
+# # # # #
+# Quiet hours, during which the charging, discharging, full and low
+# notifications are held back. Critical notifications always go through
+# `mode` suppress | defer (default {quiet_mode}), deferred notifications
+# are summarized in one notification when quiet hours end
+# `ranges` list of time ranges, `days` is optional (every day if omitted).
+#   A range ending before it starts spans midnight, `from` equal to `to`
+#   spans a whole day, eg. "00:00" to "00:00"
+# Disabled if omitted
+# [quiet_hours]
+# mode = "{quiet_mode}"
+# ranges = [
+#   {{ from = "22:00", to = "07:30" }},
+#   {{ from = "13:00", to = "15:00", days = ["sat", "sun"] }},
+# ]
+
+# # # # #
+# Profiles, applied over this config while their conditions hold. A
+# profile takes any option of this file, notifications included, and its
+# values override the ones above key by key. The profiles are applied in
+# order, a later one wins over an earlier one
+# `name` the profile name (required)
+# `when` the conditions, all must hold:
+#   `ac` true on AC, false on battery
+#   `time` list of time ranges, like the `ranges` of quiet hours
+#   `device` a power supply in `/sys/class/power_supply/`, eg. a dock or
+#   a charger, present and online
+# An empty `backends` silences notifications
+# [[profile]]
+# name = "evening"
+# when = {{ time = [{{ from = "18:00", to = "23:59" }}] }}
+# low_level = 30
+#
+# [[profile]]
+# name = "docked"
+# when = {{ ac = true, device = "ucsi-source-psy-USBC000:001" }}
+# backends = []
+
+# # # # #
+# Notifications settings
+# If you omit one, the corresponding notification is disabled
+# They take the following properties:
+# `summary` main notification text, oneline (required)
+# `body` optional multiline text
+# `icon` optional icon name (from a freedesktop.org-compliant icon theme)
+# `urgency` optional urgency level, low | normal | critical
+# `min_interval` optional minimum time between two notifications, in second
+# `close_on_exit` optional, close the notification when leaving its state,
+#   eg. the critical popup when charging starts (default true)
+# `actions` optional list of action buttons, snooze | suspend | hibernate
+#   | dismiss. `snooze` holds back all but critical notifications for
+#   `snooze` minutes, `dismiss` silences this notification until bato
+#   restarts. No action by default
+# Optional hints and properties, support depends on the notification server
+# `value` show the battery level as a progress bar (bool)
+# `category` eg. "device.battery"
+# `stack_tag` x-dunst-stack-tag, notifications with the same tag replace
+#   each other (dunst)
+# `synchronous` x-canonical-private-synchronous, same as `stack_tag` for
+#   other servers
+# `expire_timeout` in millisecond, 0 never expires
+# `transient` bypass the server persistence (bool)
+# `resident` stay in the server after an action is invoked (bool)
+# `sound_name` a freedesktop sound theme name, eg. "battery-low"
+# `sound_file` path to a sound file
+# `app_name` defaults to the program name
+# `desktop_entry` the desktop file name, without the .desktop suffix
+# `backends` optional, the backends of this notification, overriding the
+#   global `backends`
+# `summary` and `body` are templates, the following placeholders are
+# replaced by their current value: {{level}} {{status}} {{time_remaining}}
+# {{power_w}} {{battery}} {{health}} {{state}} {{prev_state}}
+# They accept a format spec like {{power_w:.1}} or {{level:>3}}, `?` is
+# shown when a value is not available
+
+[charging]
+summary = "Battery"
+body = "Charging"
+icon = "battery-good-charging"
+
+[discharging]
+summary = "Battery"
+body = "Discharging"
+icon = "battery-good"
+
+[full]
+summary = "Battery"
+body = "Full"
+icon = "battery-full"
+
+[low]
+summary = "Battery"
+body = "Low, {{level}}%"
+icon = "battery-low"
+category = "device.battery"
+value = true
+# actions = ["snooze", "dismiss"]
+
+[critical]
+summary = "Battery"
+body = "Critical!"
+icon = "battery-caution"
+urgency = "critical"
+# actions = ["hibernate"]
+"##,
+        tick_rate = DEFAULT_TICK_RATE,
+        adaptive_tick = DEFAULT_ADAPTIVE_TICK,
+        min_tick_rate = DEFAULT_MIN_TICK_RATE,
+        max_tick_rate = DEFAULT_MAX_TICK_RATE,
+        critical_level = DEFAULT_CRITICAL_LEVEL,
+        low_level = DEFAULT_LOW_LEVEL,
+        full_design = DEFAULT_FULL_DESIGN,
+        ac_debounce = DEFAULT_AC_DEBOUNCE,
+        watch_config = DEFAULT_WATCH_CONFIG,
+        output_format = OutputFormat::default().as_ref(),
+        webhook_timeout = DEFAULT_WEBHOOK_TIMEOUT,
+        max_attempts = DEFAULT_RETRY_MAX_ATTEMPTS,
+        interval = DEFAULT_RETRY_INTERVAL,
+        replace = DEFAULT_REPLACE,
+        snooze = DEFAULT_SNOOZE,
+        icon_mode = IconMode::default().as_ref(),
+        icon_step = DEFAULT_ICON_STEP,
+        quiet_mode = QuietMode::default().as_ref(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bato_toml_is_the_reference() {
+        assert_eq!(
+            include_str!("../bato.toml"),
+            reference(),
+            "bato.toml is outdated, regenerate it with `bato print-default-config`"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::{Context, Result, anyhow, bail};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+use toml_edit::{DocumentMut, Item, TableLike};
+use tracing::{debug, instrument};
+
+use crate::check::{self, Layer};
+use crate::config::{self, Config, UserConfig};
+use crate::{default_config, runtime, util};
+
+const NEW_FILE_HEADER: &str = "# Overrides the system config, see `bato print-default-config`\n\n";
+
+/// Print the value of an option, `key` being a dotted path like
+/// `low.urgency`. The config files are merged over the built-in config,
+/// so an option left to its default prints it. Returns false if there is
+/// no such option
+#[instrument]
+pub fn config_get(path: Option<PathBuf>, key: &str) -> Result<bool> {
+    let files = match path {
+        Some(path) => vec![path],
+        None => config::find_files(),
+    };
+    let mut merged: Table = default_config::reference()
+        .parse()
+        .context("failed to parse the built-in config")?;
+    for file in files {
+        let layer = Layer::read(&file)?;
+        let table = layer
+            .content
+            .parse::<Table>()
+            .with_context(|| format!("failed to parse {}", layer.path.display()))?;
+        check::merge_table(&mut merged, table);
+    }
+    let mut value = None;
+    for (i, part) in key.split('.').enumerate() {
+        value = match i {
+            0 => merged.get(part),
+            _ => value.and_then(|v: &Value| v.get(part)),
+        };
+    }
+    match value {
+        Some(Value::String(s)) => println!("{s}"),
+        Some(Value::Table(t)) => print!("{}", toml::to_string(t)?),
+        Some(v) => println!("{v}"),
+        None => match unknown_option(key) {
+            Some(message) => {
+                eprintln!("{message}");
+                return Ok(false);
+            }
+            // eg. `bat_name`, without default value
+            None => eprintln!("`{key}` is not set"),
+        },
+    }
+    Ok(true)
+}
+
+// why `key` is not an option, `None` if it is one. A config holding only
+// the key is deserialized, an unknown key is rejected
+fn unknown_option(key: &str) -> Option<String> {
+    let config = key
+        .rsplit('.')
+        .fold(Value::String(String::new()), |value, part| {
+            Value::Table(Table::from_iter([(part.to_string(), value)]))
+        });
+    let message = config.try_into::<UserConfig>().err()?.message().to_string();
+    match message.starts_with("unknown field") {
+        true => Some(check::suggest(&message)),
+        // a key below a scalar or an array, eg. `snooze.value`
+        false if message.starts_with("invalid type: map") => {
+            Some(format!("`{key}` is not an option"))
+        }
+        false => None,
+    }
+}
+
+/// Set an option in the config file, keeping its comments and layout. The
+/// result is validated before being written, then the running daemon is
+/// asked to reload. Returns false if the result is invalid
+#[instrument]
+pub fn config_set(path: Option<PathBuf>, key: &str, raw: &str) -> Result<bool> {
+    let file = match &path {
+        Some(path) => path.clone(),
+        None => Config::default_file()?,
+    };
+    // the other layers of the config, the edited file replacing its
+    // previous content
+    let mut layers = match path {
+        Some(_) => vec![],
+        None => config::find_files()
+            .iter()
+            .filter(|f| **f != file)
+            .map(|f| Layer::read(f))
+            .collect::<Result<Vec<Layer>>>()?,
+    };
+    let content = match (file.exists(), layers.is_empty()) {
+        (true, _) => fs::read_to_string(&file)
+            .with_context(|| format!("failed to read config file {}", file.display()))?,
+        // the only config file, start from the built-in config it replaces
+        (false, true) => default_config::reference(),
+        // layered over the system config, hold the edited keys only
+        (false, false) => String::new(),
+    };
+    let mut document: DocumentMut = content
+        .parse()
+        .with_context(|| format!("failed to parse {}", file.display()))?;
+    let path_parts: Vec<&str> = key.split('.').collect();
+    set_value(&mut document, &path_parts, parse_value(raw))?;
+    let content = match content.is_empty() {
+        true => format!("{NEW_FILE_HEADER}{document}"),
+        false => document.to_string(),
+    };
+
+    let drop_ins = file.with_file_name(config::DROP_IN_DIR);
+    let position = layers
+        .iter()
+        .position(|l| l.path.starts_with(&drop_ins))
+        .unwrap_or(layers.len());
+    layers.insert(
+        position,
+        Layer {
+            path: file.clone(),
+            content: content.clone(),
+        },
+    );
+    if let Err(diagnostics) = check::check(&layers) {
+        eprintln!("{}", check::render(&diagnostics, &layers));
+        eprintln!("{} left unchanged", file.display());
+        return Ok(false);
+    }
+
+    write(&file, &content)?;
+    println!("{key} = {} in {}", raw, file.display());
+    match runtime::signal_reload()? {
+        true => println!("bato reloaded"),
+        false => debug!("bato is not running"),
+    }
+    Ok(true)
+}
+
+// a value given on the command line, a value that is not valid toml is
+// taken as a string, eg. `critical`
+fn parse_value(raw: &str) -> toml_edit::Value {
+    raw.parse::<toml_edit::Value>()
+        .unwrap_or_else(|_| toml_edit::Value::from(raw))
+}
+
+/// Set the value at the given path, creating the missing tables. The
+/// comments around a replaced value are kept
+pub fn set_value(
+    document: &mut DocumentMut,
+    path: &[&str],
+    mut value: toml_edit::Value,
+) -> Result<()> {
+    let (last, parents) = path.split_last().ok_or_else(|| anyhow!("empty key"))?;
+    let mut table: &mut dyn TableLike = document.as_table_mut();
+    for key in parents {
+        if table.get(key).is_none() {
+            // no header for the intermediate tables, eg. `[backend]`
+            let mut new_table = toml_edit::Table::new();
+            new_table.set_implicit(true);
+            table.insert(key, Item::Table(new_table));
+        }
+        table = table
+            .get_mut(key)
+            .and_then(|item| item.as_table_like_mut())
+            .ok_or_else(|| anyhow!("`{key}` is not a table"))?;
+    }
+    value.decor_mut().clear();
+    match table.get_mut(last) {
+        Some(Item::Value(old)) => {
+            *value.decor_mut() = old.decor().clone();
+            *old = value;
+        }
+        Some(item) if item.is_table_like() => {
+            bail!("`{}` is a table, set its keys instead", path.join("."))
+        }
+        _ => {
+            table.insert(last, Item::Value(value));
+        }
+    }
+    Ok(())
+}
+
+/// Write then rename, so a failure never leaves a truncated config
+pub fn write(file: &Path, content: &str) -> Result<()> {
+    if let Some(dir) = file.parent().filter(|d| !d.as_os_str().is_empty()) {
+        util::check_dir_or_create(&dir.to_path_buf())?;
+    }
+    let tmp_file = file.with_extension("toml.tmp");
+    fs::write(&tmp_file, content)
+        .and_then(|_| fs::rename(&tmp_file, file))
+        .with_context(|| format!("failed to write config file {}", file.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(content: &str, key: &str, raw: &str) -> Result<String> {
+        let mut document: DocumentMut = content.parse()?;
+        let path: Vec<&str> = key.split('.').collect();
+        set_value(&mut document, &path, parse_value(raw))?;
+        Ok(document.to_string())
+    }
+
+    #[test]
+    fn values_are_typed() {
+        assert_eq!(set("", "snooze", "10").unwrap(), "snooze = 10\n");
+        assert_eq!(set("", "replace", "false").unwrap(), "replace = false\n");
+        assert_eq!(set("", "ratio", "0.5").unwrap(), "ratio = 0.5\n");
+        assert_eq!(
+            set("", "backends", r#"["desktop", "wall"]"#).unwrap(),
+            "backends = [\"desktop\", \"wall\"]\n"
+        );
+        // not toml, taken as a string
+        assert_eq!(
+            set("", "low.urgency", "critical").unwrap(),
+            "[low]\nurgency = \"critical\"\n"
+        );
+        assert_eq!(
+            set("", "bat_name", "\"10\"").unwrap(),
+            "bat_name = \"10\"\n"
+        );
+        assert_eq!(
+            set("", "low.body", "Low, {level}%").unwrap(),
+            "[low]\nbody = \"Low, {level}%\"\n"
+        );
+    }
+
+    #[test]
+    fn comments_are_kept() {
+        let content = "# tick rate\ntick_rate = 5 # in second\n\n[low]\nsummary = \"a\"\n";
+        assert_eq!(
+            set(content, "tick_rate", "10").unwrap(),
+            "# tick rate\ntick_rate = 10 # in second\n\n[low]\nsummary = \"a\"\n"
+        );
+    }
+
+    #[test]
+    fn intermediate_tables_are_implicit() {
+        assert_eq!(
+            set("", "backend.webhook.url", "http://localhost").unwrap(),
+            "[backend.webhook]\nurl = \"http://localhost\"\n"
+        );
+    }
+
+    #[test]
+    fn options_without_value_exist() {
+        assert!(unknown_option("bat_name").is_none());
+        assert!(unknown_option("quiet_hours.mode").is_none());
+        assert!(unknown_option("backend.webhook.url").is_none());
+        assert!(
+            unknown_option("snoze")
+                .unwrap()
+                .contains("did you mean `snooze`")
+        );
+        assert!(unknown_option("low.sumary").is_some());
+        assert!(unknown_option("snooze.value").is_some());
+    }
+
+    #[test]
+    fn tables_are_not_replaced() {
+        assert!(set("[low]\nsummary = \"a\"\n", "low", "1").is_err());
+        assert!(set("snooze = 5\n", "snooze.value", "1").is_err());
+    }
+}
@@ -32,11 +32,42 @@ where
         }
     }
 
+    pub fn current_state(&self) -> &K {
+        &self.current_state
+    }
+
+    /// Set the current state without running the exit/enter hooks
+    #[instrument(skip_all)]
+    pub fn restore(&mut self, state: K) {
+        debug!("fsm restore {state}");
+        self.current_state = state;
+    }
+
+    /// The state the data leads to from the current state, without running
+    /// the enter/exit hooks
+    pub fn settle(&self, data: &D) -> K
+    where
+        K: Copy,
+    {
+        let mut state = self.current_state;
+        // a state leads to another one at most once per state
+        for _ in 0..self.states.len() {
+            match self.states[&state].next_state(data) {
+                Some(next) if next != state => state = next,
+                _ => break,
+            }
+        }
+        state
+    }
+
     #[instrument(skip_all)]
     fn set_state(&mut self, new_state: K, data: &D) {
         info!("new state {new_state}");
         self.states.get_mut(&self.current_state).unwrap().exit(data);
-        self.states.get_mut(&new_state).unwrap().enter(data);
+        self.states
+            .get_mut(&new_state)
+            .unwrap()
+            .enter(&self.current_state, data);
         self.current_state = new_state;
     }
 
@@ -58,7 +89,7 @@ pub trait FsmState<K, D>: Debug
 where
     K: Eq + Hash,
 {
-    fn enter(&self, data: &D);
+    fn enter(&self, prev: &K, data: &D);
     fn next_state(&self, data: &D) -> Option<K>;
     fn exit(&self, data: &D);
 }
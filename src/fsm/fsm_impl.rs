@@ -2,7 +2,9 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
+use std::time::Duration;
 use std::{collections::HashMap, hash::Hash};
 use tracing::warn;
 
@@ -12,7 +14,7 @@ use super::fsm::{Fsm, StateMap};
 use crate::Config;
 
 // https://github.com/torvalds/linux/blob/5472d60c129f75282d94ae5ad072ee6dfb7c7246/include/linux/power_supply.h#L36
-#[derive(Hash, Eq, PartialEq, Debug, Copy, Clone, strum::AsRefStr)]
+#[derive(Hash, Eq, PartialEq, Debug, Copy, Clone, strum::AsRefStr, Serialize, Deserialize)]
 pub enum PsStatus {
     Unknown,
     Full,
@@ -21,7 +23,7 @@ pub enum PsStatus {
     Discharging,
 }
 
-#[derive(Hash, Eq, PartialEq, Debug, strum::Display)]
+#[derive(Hash, Eq, PartialEq, Debug, Copy, Clone, strum::Display, Serialize, Deserialize)]
 pub enum State {
     Charging,
     Discharging,
@@ -31,10 +33,30 @@ pub enum State {
     Critical,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Data {
+    pub battery: String,
     pub current_level: u32,
     pub status: PsStatus,
+    /// Drain or charge rate, in percent per hour
+    pub rate: Option<f64>,
+    /// Power drawn or supplied, in watt
+    pub power: Option<f64>,
+    /// Last full capacity relative to the design capacity, as a percentage
+    pub health: Option<u32>,
+}
+
+impl Data {
+    /// Estimated time until empty when discharging, until full when charging
+    pub fn time_remaining(&self) -> Option<Duration> {
+        let rate = self.rate?;
+        let left = match self.status {
+            PsStatus::Discharging => self.current_level,
+            PsStatus::Charging => 100_u32.saturating_sub(self.current_level),
+            _ => return None,
+        };
+        Some(Duration::from_secs_f64(left as f64 / rate * 3600.))
+    }
 }
 
 impl Display for Data {
@@ -44,7 +66,11 @@ impl Display for Data {
             "level {}, status {}",
             self.current_level,
             self.status.as_ref()
-        )
+        )?;
+        if let Some(rate) = self.rate {
+            write!(f, ", rate {rate:.1}%/h")?;
+        }
+        Ok(())
     }
 }
 
@@ -12,11 +12,11 @@ pub struct ChargingState(pub Config);
 
 impl FsmState<State, Data> for ChargingState {
     #[instrument(skip_all, fields(current = "charging"))]
-    fn enter(&self, _: &Data) {
+    fn enter(&self, prev: &State, data: &Data) {
         trace!("enter");
         if let Some(n) = self.0.charging.as_ref() {
             info!("sending notification");
-            crate::notify(n).ok();
+            crate::notify(&self.0, State::Charging, *prev, data, n).ok();
         }
     }
 
@@ -36,6 +36,9 @@ impl FsmState<State, Data> for ChargingState {
     #[instrument(skip_all, fields(current = "charging"))]
     fn exit(&self, _data: &Data) {
         trace!("exit");
+        if let Some(n) = self.0.charging.as_ref() {
+            crate::notifier::expire(State::Charging, n);
+        }
     }
 }
 
@@ -12,11 +12,11 @@ pub struct CriticalState(pub Config);
 
 impl FsmState<State, Data> for CriticalState {
     #[instrument(skip_all, fields(current = "critical"))]
-    fn enter(&self, _: &Data) {
+    fn enter(&self, prev: &State, data: &Data) {
         trace!("enter");
         if let Some(n) = self.0.critical.as_ref() {
             info!("sending notification");
-            crate::notify(n).ok();
+            crate::notify(&self.0, State::Critical, *prev, data, n).ok();
         }
     }
 
@@ -32,6 +32,9 @@ impl FsmState<State, Data> for CriticalState {
     #[instrument(skip_all, fields(current = "critical"))]
     fn exit(&self, _data: &Data) {
         trace!("exit");
+        if let Some(n) = self.0.critical.as_ref() {
+            crate::notifier::expire(State::Critical, n);
+        }
     }
 }
 
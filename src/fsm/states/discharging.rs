@@ -12,11 +12,11 @@ pub struct DischargingState(pub Config);
 
 impl FsmState<State, Data> for DischargingState {
     #[instrument(skip_all, fields(current = "discharging"))]
-    fn enter(&self, _: &Data) {
+    fn enter(&self, prev: &State, data: &Data) {
         trace!("enter");
         if let Some(n) = self.0.discharging.as_ref() {
             info!("sending notification");
-            crate::notify(n).ok();
+            crate::notify(&self.0, State::Discharging, *prev, data, n).ok();
         }
     }
 
@@ -36,6 +36,9 @@ impl FsmState<State, Data> for DischargingState {
     #[instrument(skip_all, fields(current = "discharging"))]
     fn exit(&self, _data: &Data) {
         trace!("exit");
+        if let Some(n) = self.0.discharging.as_ref() {
+            crate::notifier::expire(State::Discharging, n);
+        }
     }
 }
 
@@ -12,11 +12,11 @@ pub struct FullState(pub Config);
 
 impl FsmState<State, Data> for FullState {
     #[instrument(skip_all, fields(current = "full"))]
-    fn enter(&self, _: &Data) {
+    fn enter(&self, prev: &State, data: &Data) {
         trace!("enter");
         if let Some(n) = self.0.full.as_ref() {
             info!("sending notification");
-            crate::notify(n).ok();
+            crate::notify(&self.0, State::Full, *prev, data, n).ok();
         }
     }
 
@@ -34,6 +34,9 @@ impl FsmState<State, Data> for FullState {
     #[instrument(skip_all, fields(current = "full"))]
     fn exit(&self, _data: &Data) {
         trace!("exit");
+        if let Some(n) = self.0.full.as_ref() {
+            crate::notifier::expire(State::Full, n);
+        }
     }
 }
 
@@ -12,11 +12,11 @@ pub struct LowState(pub Config);
 
 impl FsmState<State, Data> for LowState {
     #[instrument(skip_all, fields(current = "low"))]
-    fn enter(&self, _: &Data) {
+    fn enter(&self, prev: &State, data: &Data) {
         trace!("enter");
         if let Some(n) = self.0.low.as_ref() {
             info!("sending notification");
-            crate::notify(n).ok();
+            crate::notify(&self.0, State::Low, *prev, data, n).ok();
         }
     }
 
@@ -33,6 +33,9 @@ impl FsmState<State, Data> for LowState {
     #[instrument(skip_all, fields(current = "low"))]
     fn exit(&self, _data: &Data) {
         trace!("exit");
+        if let Some(n) = self.0.low.as_ref() {
+            crate::notifier::expire(State::Low, n);
+        }
     }
 }
 
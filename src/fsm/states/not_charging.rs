@@ -11,7 +11,7 @@ pub struct NotChargingState;
 
 impl FsmState<State, Data> for NotChargingState {
     #[instrument(skip_all, fields(current = "not_charging"))]
-    fn enter(&self, _: &Data) {
+    fn enter(&self, _: &State, _: &Data) {
         trace!("enter");
     }
 
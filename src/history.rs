@@ -0,0 +1,39 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use tracing::trace;
+
+use crate::fsm::State;
+use crate::util;
+
+// Global notification history, shared by the fsm states and the persisted state
+pub static HISTORY: Lazy<Mutex<History>> = Lazy::new(|| Mutex::new(History::default()));
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct History {
+    /// Last time a notification was sent for a state, unix timestamp in seconds
+    pub notified: HashMap<State, u64>,
+    /// Notifications are snoozed until this time, unix timestamp in seconds
+    pub snoozed_until: Option<u64>,
+}
+
+impl History {
+    pub fn record(&mut self, state: State) {
+        let now = util::now();
+        trace!("record notification {state} at {now}");
+        self.notified.insert(state, now);
+    }
+
+    pub fn is_snoozed(&self) -> bool {
+        self.snoozed_until.is_some_and(|t| t > util::now())
+    }
+}
+
+pub fn get() -> MutexGuard<'static, History> {
+    HISTORY.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,40 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use tracing::{instrument, trace};
+
+use crate::Config;
+use crate::config::{IconMode, Notification};
+use crate::fsm::{Data, PsStatus};
+
+/// Select the icon of a notification according to the icon mode
+#[instrument(skip_all)]
+pub fn select(config: &Config, to_send: &Notification, data: &Data) -> Option<String> {
+    let charging = data.status == PsStatus::Charging;
+    let icon = match config.icon_mode {
+        IconMode::Fixed => return to_send.icon.clone(),
+        IconMode::Level => {
+            let step = config.icon_step.clamp(1, 100);
+            // round to the nearest step of the icon theme
+            let level = ((data.current_level.min(100) + step / 2) / step * step).min(100);
+            let icon = match (data.status, charging) {
+                (PsStatus::Full, _) => "battery-level-100-charged-symbolic".to_string(),
+                (_, true) => format!("battery-level-{level}-charging-symbolic"),
+                (_, false) => format!("battery-level-{level}-symbolic"),
+            };
+            Some(icon)
+        }
+        IconMode::Map => config
+            .icon_map
+            .iter()
+            .filter(|entry| entry.from <= data.current_level)
+            .max_by_key(|entry| entry.from)
+            .map(|entry| match (&entry.charging_icon, charging) {
+                (Some(icon), true) => icon.clone(),
+                _ => entry.icon.clone(),
+            }),
+    };
+    trace!("selected icon {icon:?}");
+    icon.or_else(|| to_send.icon.clone())
+}
@@ -0,0 +1,218 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use toml_edit::DocumentMut;
+use tracing::instrument;
+
+use crate::check::{self, Layer};
+use crate::config::{Config, DEFAULT_CRITICAL_LEVEL, DEFAULT_LOW_LEVEL};
+use crate::supply::{self, PowerSupply};
+use crate::{SYS_PATH, default_config, edit};
+
+const NOTIFICATIONS: [&str; 5] = ["charging", "discharging", "full", "low", "critical"];
+
+/// Detect the power supplies, ask for the main settings and write a
+/// commented config. An existing config is only replaced once confirmed,
+/// and kept as a backup
+#[instrument]
+pub fn init(path: Option<PathBuf>) -> Result<()> {
+    let file = match path {
+        Some(path) => path,
+        None => Config::default_file()?,
+    };
+    if file.exists() && !ask_yes_no(&format!("{} exists, replace it?", file.display()), false)? {
+        println!("{} left unchanged", file.display());
+        return Ok(());
+    }
+
+    let supplies = supply::list().unwrap_or_default();
+    print_supplies(&supplies);
+    let batteries: Vec<&PowerSupply> = supplies.iter().filter(|s| s.is_battery()).collect();
+    let battery = match batteries.as_slice() {
+        [] => {
+            println!("no battery found, bato will look for one when it starts");
+            None
+        }
+        [battery] => Some(battery.name.clone()),
+        [first, ..] => Some(ask_battery(&batteries, &first.name)?),
+    };
+    if let Some(battery) = batteries.iter().find(|b| Some(&b.name) == battery.as_ref())
+        && battery.unit.is_none()
+    {
+        println!(
+            "warning: {} reports neither energy nor charge, bato cannot monitor it",
+            battery.name
+        );
+    }
+
+    let critical_level = ask_level("Critical level, in %", DEFAULT_CRITICAL_LEVEL, 99)?;
+    let low_level = loop {
+        let level = ask_level(
+            "Low level, in %",
+            DEFAULT_LOW_LEVEL.max(critical_level + 1),
+            100,
+        )?;
+        match level > critical_level {
+            true => break level,
+            false => println!("the low level must be above the critical level ({critical_level})"),
+        }
+    };
+    let mut disabled = vec![];
+    for notification in NOTIFICATIONS {
+        if !ask_yes_no(&format!("Notify when {notification}?"), true)? {
+            disabled.push(notification);
+        }
+    }
+
+    let reference = match &battery {
+        Some(name) => default_config::with_battery(name),
+        None => default_config::reference(),
+    };
+    let mut document: DocumentMut = reference.parse()?;
+    edit::set_value(
+        &mut document,
+        &["critical_level"],
+        i64::from(critical_level).into(),
+    )?;
+    edit::set_value(&mut document, &["low_level"], i64::from(low_level).into())?;
+    let mut content = document.to_string();
+    for notification in disabled {
+        content = comment_out(&content, notification);
+    }
+
+    let layer = Layer {
+        path: file.clone(),
+        content,
+    };
+    if let Err(diagnostics) = check::check(std::slice::from_ref(&layer)) {
+        bail!(
+            "the generated config is invalid\n{}",
+            check::render(&diagnostics, &[layer])
+        );
+    }
+    if file.exists() {
+        let backup = file.with_extension("toml.bak");
+        fs::copy(&file, &backup)
+            .with_context(|| format!("failed to back up {}", file.display()))?;
+        println!("previous config saved to {}", backup.display());
+    }
+    edit::write(&file, &layer.content)?;
+    println!("config written to {}", file.display());
+    Ok(())
+}
+
+fn print_supplies(supplies: &[PowerSupply]) {
+    println!("power supplies found in {SYS_PATH}");
+    let width = supplies
+        .iter()
+        .map(|s| s.name.len())
+        .max()
+        .unwrap_or_default();
+    for supply in supplies {
+        let (role, details) = match supply {
+            s if s.is_battery() => ("battery", battery_details(s)),
+            s if s.is_ac() => (
+                "ac",
+                match s.online {
+                    Some(true) => "online".to_string(),
+                    Some(false) => "offline".to_string(),
+                    None => String::new(),
+                },
+            ),
+            s if s.is_peripheral() => ("peripheral", battery_details(s)),
+            _ => ("other", supply.kind.clone().unwrap_or_default()),
+        };
+        println!("  {role:<10} {:<width$}  {details}", supply.name);
+    }
+}
+
+fn battery_details(supply: &PowerSupply) -> String {
+    if supply.present == Some(false) {
+        return "not present".to_string();
+    }
+    [
+        supply.unit_name().map(String::from),
+        supply.capacity.map(|c| format!("{c}%")),
+        supply.status.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .collect::<Vec<String>>()
+    .join(", ")
+}
+
+// comment out the table of a disabled notification, so it is easy to
+// enable it back. The table ends at the first empty line
+fn comment_out(content: &str, table: &str) -> String {
+    let header = format!("[{table}]");
+    let mut in_table = false;
+    let mut output = String::new();
+    for line in content.lines() {
+        if line.starts_with('[') {
+            in_table = line == header;
+        }
+        in_table &= !line.is_empty();
+        match in_table {
+            true => output.push_str(&format!("# {line}\n")),
+            false => output.push_str(&format!("{line}\n")),
+        }
+    }
+    output
+}
+
+// print a question and read the answer, `None` on an empty answer or when
+// stdin is closed
+fn ask(question: &str, hint: &str) -> Result<Option<String>> {
+    print!("{question} [{hint}] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    if io::stdin().lock().read_line(&mut answer)? == 0 {
+        println!();
+    }
+    let answer = answer.trim();
+    Ok((!answer.is_empty()).then(|| answer.to_string()))
+}
+
+fn ask_yes_no(question: &str, default: bool) -> Result<bool> {
+    let hint = match default {
+        true => "Y/n",
+        false => "y/N",
+    };
+    loop {
+        match ask(question, hint)?.map(|a| a.to_lowercase()).as_deref() {
+            None => return Ok(default),
+            Some("y" | "yes") => return Ok(true),
+            Some("n" | "no") => return Ok(false),
+            _ => println!("answer yes or no"),
+        }
+    }
+}
+
+fn ask_level(question: &str, default: u32, max: u32) -> Result<u32> {
+    loop {
+        let Some(answer) = ask(question, &default.to_string())? else {
+            return Ok(default);
+        };
+        match answer.parse::<u32>() {
+            Ok(level) if level <= max => return Ok(level),
+            _ => println!("expected a number between 0 and {max}"),
+        }
+    }
+}
+
+fn ask_battery(batteries: &[&PowerSupply], default: &str) -> Result<String> {
+    loop {
+        let Some(name) = ask("Battery to monitor", default)? else {
+            return Ok(default.to_string());
+        };
+        if batteries.iter().any(|b| b.name == name) {
+            return Ok(name);
+        }
+        println!("unknown battery {name}");
+    }
+}
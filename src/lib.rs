@@ -2,26 +2,60 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+mod action;
+mod bar;
+mod check;
 pub mod cli;
 mod config;
+mod default_config;
+mod edit;
 mod fsm;
+mod history;
+mod icon;
+mod init;
+mod notifier;
+mod persist;
+mod preview;
+mod profile;
+mod quiet;
+mod reload;
+mod resume;
+mod runtime;
 pub mod signal;
+mod status;
+mod supply;
+mod template;
+mod tick;
 pub mod trace;
 mod util;
 
 use anyhow::{Context, Result, anyhow, bail};
 use fsm::{Data, Fsm, PsStatus, State};
-use mio::{Events, Interest, Poll, Token};
-use once_cell::sync::Lazy;
+use mio::{Events, Interest, Poll, Token, Waker};
+use once_cell::sync::{Lazy, OnceCell};
 use std::convert::TryFrom;
 use std::fs::{self, DirEntry};
 use std::io::ErrorKind;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, instrument, trace, warn};
 
+pub use crate::bar::{BarFormat, run as bar};
+pub use crate::check::check_config;
 pub use crate::config::Config;
-use crate::config::Notification;
+use crate::config::{Notification, QuietMode};
+pub use crate::default_config::reference as reference_config;
+pub use crate::edit::{config_get, config_set};
+pub use crate::init::init;
+use crate::notifier::Message;
+pub use crate::notifier::{Server, probe_server};
+use crate::persist::{BatteryId, Snapshot};
+pub use crate::preview::test_notify;
+use crate::reload::ConfigWatcher;
+use crate::resume::{BootTimer, ResumeDetector};
+pub use crate::status::Status;
+pub use crate::supply::print as list_power_supplies;
+use crate::template::Value;
 
 const UDEV_SUBSYSTEM: &str = "power_supply";
 const SYS_PATH: &str = "/sys/class/power_supply/";
@@ -32,8 +66,21 @@ const ENERGY_PREFIX: &str = "ENERGY";
 const FULL_ATTRIBUTE: &str = "FULL";
 const FULL_DESIGN_ATTRIBUTE: &str = "FULL_DESIGN";
 const NOW_ATTRIBUTE: &str = "NOW";
+const POWER_NOW_ATTRIBUTE: &str = "POWER_SUPPLY_POWER_NOW";
+const CURRENT_NOW_ATTRIBUTE: &str = "POWER_SUPPLY_CURRENT_NOW";
+const VOLTAGE_NOW_ATTRIBUTE: &str = "POWER_SUPPLY_VOLTAGE_NOW";
+const TEMP_ATTRIBUTE: &str = "POWER_SUPPLY_TEMP";
 const STATUS_ATTRIBUTE: &str = "POWER_SUPPLY_STATUS";
 const ONLINE_ATTRIBUTE: &str = "POWER_SUPPLY_ONLINE";
+const MANUFACTURER_ATTRIBUTE: &str = "POWER_SUPPLY_MANUFACTURER";
+const MODEL_ATTRIBUTE: &str = "POWER_SUPPLY_MODEL_NAME";
+const SERIAL_ATTRIBUTE: &str = "POWER_SUPPLY_SERIAL_NUMBER";
+const TYPE_ATTRIBUTE: &str = "POWER_SUPPLY_TYPE";
+const SCOPE_ATTRIBUTE: &str = "POWER_SUPPLY_SCOPE";
+const PRESENT_ATTRIBUTE: &str = "POWER_SUPPLY_PRESENT";
+const CAPACITY_ATTRIBUTE: &str = "POWER_SUPPLY_CAPACITY";
+const MAINS_TYPE: &str = "Mains";
+const BATTERY_TYPE: &str = "Battery";
 const XDG_CONFIG_HOME: &str = "XDG_CONFIG_HOME";
 const APP_DIR: &str = "bato";
 const CONFIG_FILE: &str = "bato.toml";
@@ -41,14 +88,52 @@ const CONFIG_FILE: &str = "bato.toml";
 // Global application state, used to terminate the main-loop and all modules
 pub static RUN: Lazy<AtomicBool> = Lazy::new(|| AtomicBool::new(true));
 
+// Wakes the main-loop up from other threads
+static WAKER: OnceCell<Waker> = OnceCell::new();
+
 #[derive(Debug)]
 pub struct Bato {
-    #[allow(dead_code)]
-    bat_name: String,
+    config: Config,
+    battery: BatteryId,
     uevent: String,
     now_attribute: String,
     full_attribute: String,
+    capacity_attribute: String,
+    design_capacity_attribute: String,
+    rate_attribute: &'static str,
     fsm: Fsm<State, Data>,
+    status: Option<PsStatus>,
+    data: Option<Data>,
+    // the active profiles
+    profiles: Vec<String>,
+    // serves the status to `bato bar`
+    bar: Option<bar::Publisher>,
+}
+
+/// Attributes read from /sys/class/power_supply/<BAT_NAME>/uevent
+#[derive(Debug)]
+struct Attributes {
+    now: i32,
+    full: i32,
+    status: PsStatus,
+    // power (energy unit) or current (charge unit) drawn or supplied
+    rate: Option<i32>,
+    voltage: Option<i32>,
+    // last full capacity and design capacity, for the battery health
+    capacity: Option<i32>,
+    design_capacity: Option<i32>,
+    // in tenth of °C
+    temperature: Option<i32>,
+}
+
+/// Wake the main-loop up, to handle a change made by another thread
+pub fn wake() {
+    if let Some(waker) = WAKER.get() {
+        waker
+            .wake()
+            .inspect_err(|e| error!("failed to wake the main-loop: {e}"))
+            .ok();
+    }
 }
 
 // check if the given battery is present
@@ -64,26 +149,93 @@ pub fn check_system_path(battery: &Option<String>) -> Result<()> {
     Ok(())
 }
 
-#[instrument]
-pub fn notify(to_send: &Notification) -> Result<()> {
-    let mut ntf = notify_rust::Notification::new()
-        .summary(&to_send.summary)
-        .finalize();
-    if let Some(body) = to_send.body.as_ref() {
-        ntf.body(body);
+/// Send the notification of a state, unless rate limiting or quiet hours
+/// hold it back
+#[instrument(skip(config, data, to_send))]
+pub fn notify(
+    config: &Config,
+    state: State,
+    prev_state: State,
+    data: &Data,
+    to_send: &Notification,
+) -> Result<()> {
+    if action::is_dismissed(state) {
+        info!("notification dismissed for this session, skipped");
+        return Ok(());
     }
-    if let Some(icon) = to_send.icon.as_ref() {
-        ntf.icon(icon);
+    if state != State::Critical && history::get().is_snoozed() {
+        info!("notifications snoozed, skipped");
+        return Ok(());
     }
-    if let Some(urgency) = to_send.urgency.as_ref() {
-        ntf.urgency(notify_rust::Urgency::from(urgency));
+    if let Some(min_interval) = to_send.min_interval
+        && let Some(last) = history::get().notified.get(&state)
+        && util::now().saturating_sub(*last) < min_interval as u64
+    {
+        info!("notification sent less than {min_interval}s ago, skipped");
+        return Ok(());
     }
-    debug!("notify show");
-    ntf.show()
-        .inspect_err(|e| error!("failed to show notification: {e}"))?;
+    let to_send = &render(config, to_send, state, prev_state, data);
+    // critical notifications always go through
+    if state != State::Critical {
+        match quiet::active_mode(config) {
+            Some(QuietMode::Suppress) => {
+                info!("quiet hours, notification suppressed");
+                return Ok(());
+            }
+            Some(QuietMode::Defer) => {
+                info!("quiet hours, notification deferred");
+                quiet::defer(state, to_send);
+                return Ok(());
+            }
+            None => {}
+        }
+    }
+    let message = Message {
+        state: Some(state),
+        level: Some(data.current_level),
+        notification: to_send,
+    };
+    notifier::send(config, &message)?;
+    history::get().record(state);
     Ok(())
 }
 
+// the value of a template placeholder, `None` if the placeholder is unknown
+fn placeholder(name: &str, state: State, prev_state: State, data: &Data) -> Option<Option<Value>> {
+    let value = match name {
+        "level" => Some(Value::Int(data.current_level as i64)),
+        "status" => Some(Value::Str(data.status.as_ref().to_string())),
+        "time_remaining" => data
+            .time_remaining()
+            .map(|d| Value::Str(util::format_duration(d))),
+        "power_w" => data.power.map(Value::Float),
+        "battery" => Some(Value::Str(data.battery.clone())),
+        "health" => data.health.map(|h| Value::Int(h as i64)),
+        "state" => Some(Value::Str(state.to_string())),
+        "prev_state" => Some(Value::Str(prev_state.to_string())),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Render the templated summary and body of a notification, and select
+/// its icon
+fn render(
+    config: &Config,
+    to_send: &Notification,
+    state: State,
+    prev_state: State,
+    data: &Data,
+) -> Notification {
+    let lookup = |name: &str| placeholder(name, state, prev_state, data);
+    Notification {
+        summary: template::render(&to_send.summary, lookup),
+        body: to_send.body.as_ref().map(|b| template::render(b, lookup)),
+        icon: icon::select(config, to_send, data),
+        ..to_send.clone()
+    }
+}
+
 // find a battery in `/sys/class/power_supply/`
 #[instrument]
 pub fn find_battery() -> Result<Option<String>> {
@@ -110,6 +262,66 @@ pub fn find_battery() -> Result<Option<String>> {
     Ok(entry)
 }
 
+// get the value of an attribute from the content of an uevent file
+fn uevent_attribute<'a>(content: &'a str, key: &str) -> Option<&'a str> {
+    content
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v)
+}
+
+// read the AC state from the first mains power supply found in
+// `/sys/class/power_supply/`
+#[instrument]
+fn read_ac_online() -> Result<Option<bool>> {
+    let online = fs::read_dir(SYS_PATH)
+        .inspect_err(|e| error!("failed to read dir {}: {}", SYS_PATH, e))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| fs::read_to_string(entry.path().join(UEVENT)).ok())
+        .find_map(|content| {
+            if uevent_attribute(&content, TYPE_ATTRIBUTE) != Some(MAINS_TYPE) {
+                return None;
+            }
+            uevent_attribute(&content, ONLINE_ATTRIBUTE).map(|v| v == "1")
+        });
+    debug!("AC online: {online:?}");
+    Ok(online)
+}
+
+// the last AC state carried by the pending udev events, if any. All the
+// events are drained, the socket being edge-triggered
+fn ac_event(socket: &udev::MonitorSocket) -> Option<bool> {
+    socket
+        .iter()
+        .filter(|e| e.sysname() == "AC")
+        .inspect(|_| info!("AC udev event"))
+        .filter_map(|e| {
+            e.property_value(ONLINE_ATTRIBUTE)
+                .and_then(|v| v.to_str())
+                .and_then(|v| match v {
+                    "1" => Some(true),
+                    "0" => Some(false),
+                    _ => None,
+                })
+        })
+        .last()
+}
+
+// read the identity of the battery from its uevent file
+#[instrument]
+fn read_battery_id(name: &str, uevent: &str) -> Result<BatteryId> {
+    let content =
+        fs::read_to_string(uevent).inspect_err(|e| error!("failed to read {uevent}: {e}"))?;
+    let attribute = |key| uevent_attribute(&content, key).map(String::from);
+    Ok(BatteryId {
+        name: name.to_string(),
+        manufacturer: attribute(MANUFACTURER_ATTRIBUTE),
+        model: attribute(MODEL_ATTRIBUTE),
+        serial: attribute(SERIAL_ATTRIBUTE),
+    })
+}
+
 impl Bato {
     #[instrument(skip_all)]
     pub fn with_config(config: Config) -> Result<Self> {
@@ -133,21 +345,88 @@ impl Bato {
         debug!("found attribute prefix: {attribute_prefix}");
         let now_attribute = format!("{}_{}_{}", POWER_SUPPLY, attribute_prefix, NOW_ATTRIBUTE);
         let full_attribute = format!("{}_{}_{}", POWER_SUPPLY, attribute_prefix, full_attr);
+        let capacity_attribute =
+            format!("{}_{}_{}", POWER_SUPPLY, attribute_prefix, FULL_ATTRIBUTE);
+        let design_capacity_attribute = format!(
+            "{}_{}_{}",
+            POWER_SUPPLY, attribute_prefix, FULL_DESIGN_ATTRIBUTE
+        );
+        let rate_attribute = match attribute_prefix {
+            ENERGY_PREFIX => POWER_NOW_ATTRIBUTE,
+            _ => CURRENT_NOW_ATTRIBUTE,
+        };
+        let battery = read_battery_id(&bat_name, &uevent)?;
+        debug!("battery id: {:?}", battery);
         Ok(Bato {
-            bat_name,
+            fsm: fsm::create(config.clone()),
+            config,
+            battery,
             uevent,
             now_attribute,
             full_attribute,
-            fsm: fsm::create(config),
+            capacity_attribute,
+            design_capacity_attribute,
+            rate_attribute,
+            status: None,
+            data: None,
+            profiles: vec![],
+            bar: None,
         })
     }
 
+    /// Restore the fsm state and the notification history saved by a
+    /// previous run, if the battery and its status did not change since
+    #[instrument(skip(self))]
+    fn restore(&mut self) -> Result<()> {
+        let Some(snapshot) = persist::load()? else {
+            return Ok(());
+        };
+        if snapshot.battery != self.battery {
+            info!("battery changed since last run, discarding saved state");
+            return Ok(());
+        }
+        let status = self.parse_attributes().context("parse attribute")?.status;
+        if snapshot.status != status {
+            info!(
+                "battery status changed since last run ({} -> {}), discarding saved state",
+                snapshot.status.as_ref(),
+                status.as_ref()
+            );
+            return Ok(());
+        }
+        info!("restoring state {}", snapshot.state);
+        self.fsm.restore(snapshot.state);
+        self.status = Some(status);
+        *history::get() = snapshot.history;
+        Ok(())
+    }
+
+    /// Save the fsm state and the notification history
+    #[instrument(skip(self))]
+    fn persist(&self) -> Result<()> {
+        let Some(status) = self.status else {
+            return Ok(());
+        };
+        let snapshot = Snapshot {
+            battery: self.battery.clone(),
+            state: *self.fsm.current_state(),
+            status,
+            history: history::get().clone(),
+        };
+        persist::save(&snapshot)
+    }
+
     /// Read and parse attributes value in /sys/class/power_supply/<BAT_NAME>/uevent
     #[instrument(skip(self))]
-    fn parse_attributes(&self) -> Result<(i32, i32, PsStatus)> {
+    fn parse_attributes(&self) -> Result<Attributes> {
         let mut now = None;
         let mut full = None;
         let mut status = None;
+        let mut rate = None;
+        let mut voltage = None;
+        let mut capacity = None;
+        let mut design_capacity = None;
+        let mut temperature = None;
         for line in fs::read_to_string(&self.uevent)
             .inspect_err(|e| error!("failed to read {}: {e}", self.uevent))?
             .lines()
@@ -172,8 +451,21 @@ impl Bato {
             if status.is_none() && key == STATUS_ATTRIBUTE {
                 status = Some(value.to_string());
             }
-            if now.is_some() && full.is_some() && status.is_some() {
-                break;
+            if rate.is_none() && key == self.rate_attribute {
+                // some drivers report a negative value when discharging
+                rate = value.parse::<i32>().ok().map(i32::abs);
+            }
+            if voltage.is_none() && key == VOLTAGE_NOW_ATTRIBUTE {
+                voltage = value.parse().ok();
+            }
+            if capacity.is_none() && key == self.capacity_attribute {
+                capacity = value.parse().ok();
+            }
+            if design_capacity.is_none() && key == self.design_capacity_attribute {
+                design_capacity = value.parse().ok();
+            }
+            if temperature.is_none() && key == TEMP_ATTRIBUTE {
+                temperature = value.parse().ok();
             }
         }
         if now.is_none() {
@@ -197,14 +489,27 @@ impl Bato {
                 self.uevent
             );
         }
-        Ok((now.unwrap(), full.unwrap(), status.unwrap().as_str().into()))
+        Ok(Attributes {
+            now: now.unwrap(),
+            full: full.unwrap(),
+            status: status.unwrap().as_str().into(),
+            rate,
+            voltage,
+            capacity,
+            design_capacity,
+            temperature,
+        })
     }
 
     #[instrument(skip(self))]
-    pub fn run(&mut self, tick: Duration) -> Result<()> {
+    pub fn run(&mut self) -> Result<()> {
         let mut poll = Poll::new()?;
         let mut events = Events::with_capacity(128);
         const MONITOR: Token = Token(0);
+        const WAKE: Token = Token(1);
+        const CONFIG: Token = Token(2);
+        const BAR: Token = Token(3);
+        const TIMER: Token = Token(4);
 
         let mut socket = udev::MonitorBuilder::new()?
             .match_subsystem(UDEV_SUBSYSTEM)?
@@ -212,71 +517,307 @@ impl Bato {
 
         poll.registry()
             .register(&mut socket, MONITOR, Interest::READABLE)?;
+        WAKER
+            .set(Waker::new(poll.registry(), WAKE)?)
+            .map_err(|_| anyhow!("main-loop already running"))?;
+        runtime::write_pid()
+            .inspect_err(|e| warn!("failed to write pid file: {e}"))
+            .ok();
+
+        notifier::start_worker()
+            .inspect_err(|e| warn!("failed to start the notifier worker: {e}"))
+            .ok();
+        notifier::watch_server()
+            .inspect_err(|e| warn!("failed to watch the notification server: {e}"))
+            .ok();
+
+        let mut watcher = match self.config.watch_config {
+            true => ConfigWatcher::new(&self.config.watched())
+                .inspect_err(|e| warn!("failed to watch the config files: {e}"))
+                .ok(),
+            false => None,
+        };
+        if let Some(watcher) = watcher.as_mut() {
+            poll.registry()
+                .register(watcher, CONFIG, Interest::READABLE)?;
+        }
+
+        self.bar = bar::Publisher::bind()
+            .inspect_err(|e| warn!("failed to serve the bar status: {e}"))
+            .ok();
+        if let Some(publisher) = self.bar.as_mut() {
+            poll.registry()
+                .register(publisher, BAR, Interest::READABLE)?;
+        }
+
+        self.restore()
+            .inspect_err(|e| warn!("failed to restore state: {e}"))
+            .ok();
 
         // initial update
         self.update(None)
             .inspect_err(|e| error!("failed to update: {e}"))
             .ok();
 
+        // without it, the poll timeout stops during a suspend and the
+        // resume is only noticed on the next tick
+        let mut timer = BootTimer::new()
+            .inspect_err(|e| warn!("failed to create the tick timer: {e}"))
+            .ok();
+        if let Some(timer) = timer.as_mut() {
+            poll.registry().register(timer, TIMER, Interest::READABLE)?;
+        }
+        let mut resume = ResumeDetector::new();
+        debug!(
+            "tick rate {}s, adaptive {}",
+            self.config.tick_rate, self.config.adaptive_tick
+        );
+
+        // AC state waiting for the debounce window to end
+        let mut ac_pending: Option<(bool, Instant)> = None;
+
         while RUN.load(Ordering::Relaxed) {
-            match poll.poll(&mut events, Some(tick)) {
-                Ok(_) => {}
-                Err(e) if e.kind() == ErrorKind::Interrupted => {
-                    // when laptop goes into sleep poll exits with Interrupted
-                    // → retry
-                    debug!("poll interrupted, retrying");
-                    continue;
+            let tick = tick::next_tick(&self.config, self.data.as_ref());
+            trace!("next tick in {}s", tick.as_secs());
+            // wake up early for a settling AC transition or a retry
+            let deadline = [ac_pending.map(|(_, d)| d), notifier::next_due()]
+                .into_iter()
+                .flatten()
+                .min();
+            let timeout = match deadline {
+                Some(d) => tick.min(d.saturating_duration_since(Instant::now())),
+                None => tick,
+            };
+            let timeout = match timer.as_mut() {
+                Some(timer) => {
+                    timer.set(timeout)?;
+                    None
                 }
+                None => Some(timeout),
+            };
+            let interrupted = match poll.poll(&mut events, timeout) {
+                Ok(_) => false,
+                // a signal was caught
+                Err(e) if e.kind() == ErrorKind::Interrupted => true,
                 Err(e) => {
                     error!("poll error: {}", e);
                     bail!("poll error: {}", e);
                 }
+            };
+
+            if !RUN.load(Ordering::Relaxed) {
+                break;
             }
 
-            if events.is_empty() {
-                // poll timeout -> no event, just update
-                trace!("tick");
-                self.update(None)
-                    .inspect_err(|e| error!("failed to update: {e}"))
+            quiet::flush(&self.config)
+                .inspect_err(|e| error!("failed to flush deferred notifications: {e}"))
+                .ok();
+            notifier::retry(&self.config);
+
+            let changed = events.iter().any(|e| e.token() == CONFIG)
+                && watcher.as_ref().is_some_and(|w| w.changed());
+            if changed {
+                info!("config file changed");
+            }
+            if events.iter().any(|e| e.token() == BAR)
+                && let Some(publisher) = self.bar.as_mut()
+            {
+                publisher.accept();
+            }
+
+            if persist::requested() {
+                self.persist()
+                    .inspect_err(|e| error!("failed to save state: {e}"))
                     .ok();
+            }
+
+            if reload::requested() || changed {
+                self.reload()
+                    .inspect_err(|e| {
+                        error!("failed to reload config, keeping the current one: {e}")
+                    })
+                    .ok();
+            }
+
+            if let Some(suspended) = resume.check() {
+                info!("resumed after {}s of suspend", suspended.as_secs());
+                // udev events queued during suspend are outdated, sysfs is
+                // the source of truth now
+                socket.iter().for_each(drop);
+                ac_pending = None;
+                self.refresh()
+                    .inspect_err(|e| error!("failed to refresh after resume: {e}"))
+                    .ok();
+                continue;
+            }
+            if interrupted {
+                // → retry
+                debug!("poll interrupted, retrying");
+                continue;
+            }
+
+            if events.iter().all(|e| e.token() == TIMER) {
+                // poll timeout -> no event, just update
+                // unless an AC transition is settling
+                if ac_pending.is_none() {
+                    trace!("tick");
+                    self.update(None)
+                        .inspect_err(|e| error!("failed to update: {e}"))
+                        .ok();
+                }
             } else if events
                 .iter()
                 .any(|e| e.token() == MONITOR && e.is_readable())
+                && let Some(ac) = ac_event(&socket)
             {
-                let ac_online = socket
-                    .iter()
-                    .filter(|e| e.sysname() == "AC")
-                    .inspect(|_| info!("AC udev event"))
-                    .find_map(|e| {
-                        e.property_value(ONLINE_ATTRIBUTE)
-                            .and_then(|v| v.to_str())
-                            .and_then(|v| match v {
-                                "1" => Some(true),
-                                "0" => Some(false),
-                                _ => None,
-                            })
-                    });
-
-                if let Some(ac) = ac_online {
-                    debug!("AC online: {}", ac);
+                debug!("AC online: {}", ac);
+                let debounce = Duration::from_millis(self.config.ac_debounce as u64);
+                if debounce.is_zero() {
                     self.update(Some(ac))
                         .inspect_err(|e| error!("failed to update: {e}"))
                         .ok();
+                } else {
+                    ac_pending = Some((ac, Instant::now() + debounce));
                 }
             }
+
+            if let Some((ac, deadline)) = ac_pending
+                && Instant::now() >= deadline
+            {
+                debug!("AC settled, online: {}", ac);
+                ac_pending = None;
+                self.update(Some(ac))
+                    .inspect_err(|e| error!("failed to update: {e}"))
+                    .ok();
+            }
+        }
+        self.persist()
+            .inspect_err(|e| error!("failed to save state: {e}"))
+            .ok();
+        self.bar = None;
+        runtime::remove_pid();
+        Ok(())
+    }
+
+    /// Reload the config file and rebuild the fsm states, keeping the
+    /// current state. An invalid config is rejected
+    #[instrument(skip(self))]
+    pub fn reload(&mut self) -> Result<()> {
+        let config = Config::new(self.config.path.clone(), self.config.overrides.clone())?;
+        if config.bat_name != self.config.bat_name
+            || config.full_design != self.config.full_design
+            || config.watch_config != self.config.watch_config
+        {
+            warn!("bat_name, full_design and watch_config changes apply on restart");
+        }
+        self.set_config(config);
+        info!("config reloaded");
+        self.profiles.clear();
+        self.apply_profiles(None)
+    }
+
+    // rebuild the fsm states with a new config, keeping the current state
+    fn set_config(&mut self, config: Config) {
+        let state = *self.fsm.current_state();
+        self.fsm = fsm::create(config.clone());
+        self.fsm.restore(state);
+        self.config = config;
+    }
+
+    /// Apply the profiles whose conditions hold, if they changed
+    #[instrument(skip(self))]
+    fn apply_profiles(&mut self, uevent_ac: Option<bool>) -> Result<()> {
+        if self.config.profiles.is_empty() {
+            return Ok(());
+        }
+        let ac = match uevent_ac {
+            Some(ac) => Some(ac),
+            None => read_ac_online()
+                .inspect_err(|e| warn!("failed to read AC state: {e}"))
+                .ok()
+                .flatten(),
+        };
+        let profiles = profile::active(&self.config, ac);
+        if profiles == self.profiles {
+            return Ok(());
         }
+        info!("active profiles: {profiles:?}");
+        let config = self.config.profiled(&profiles)?;
+        self.set_config(config);
+        self.profiles = profiles;
         Ok(())
     }
 
+    /// Force an update, re-checking the AC state against the battery
+    /// status in case the latter has not been refreshed yet
+    #[instrument(skip(self))]
+    pub fn refresh(&mut self) -> Result<()> {
+        let status = self.parse_attributes().context("parse attribute")?.status;
+        let ac = read_ac_online()
+            .inspect_err(|e| warn!("failed to read AC state: {e}"))
+            .ok()
+            .flatten();
+        let uevent_ac = match (ac, status) {
+            (Some(true), PsStatus::Discharging) => Some(true),
+            (Some(false), PsStatus::Charging) => Some(false),
+            _ => None,
+        };
+        self.update(uevent_ac)
+    }
+
     #[instrument(skip(self))]
     pub fn update(&mut self, uevent_ac: Option<bool>) -> Result<()> {
-        let (energy, capacity, sysfs_status) =
-            self.parse_attributes().context("parse attribute")?;
+        self.apply_profiles(uevent_ac)
+            .inspect_err(|e| error!("failed to apply profiles: {e}"))
+            .ok();
+        let attributes = self.parse_attributes().context("parse attribute")?;
+        let data = self.data(&attributes, uevent_ac)?;
+        debug!("update: {}", data);
+        let prev_state = *self.fsm.current_state();
+        self.fsm.shift(&data);
+        notifier::close_expired();
+        if self.bar.is_some() {
+            let status = self.status_of(&data, &attributes, uevent_ac);
+            if let Some(publisher) = self.bar.as_mut() {
+                publisher.publish(&status);
+            }
+        }
+        if prev_state != *self.fsm.current_state() || self.status != Some(data.status) {
+            self.status = Some(data.status);
+            self.persist()
+                .inspect_err(|e| error!("failed to save state: {e}"))
+                .ok();
+        }
+        self.data = Some(data);
+        Ok(())
+    }
+
+    // compute the battery data from its attributes
+    fn data(&self, attributes: &Attributes, uevent_ac: Option<bool>) -> Result<Data> {
+        let sysfs_status = attributes.status;
         trace!("sysfs status {}", sysfs_status.as_ref());
-        let capacity = capacity as u64;
-        let energy = energy as u64;
+        let capacity = attributes.full as u64;
+        let energy = attributes.now as u64;
         let battery_level = u32::try_from(100_u64 * energy / capacity)
             .context("failed to calculate battery level")?;
+        // drain or charge rate, as a percentage of the capacity per hour
+        let rate = attributes
+            .rate
+            .filter(|r| *r > 0 && capacity > 0)
+            .map(|r| 100_f64 * r as f64 / capacity as f64);
+        // µW for energy unit, µA × µV for charge unit
+        let power = match self.rate_attribute {
+            POWER_NOW_ATTRIBUTE => attributes.rate.map(|r| r as f64 / 1e6),
+            _ => attributes
+                .rate
+                .zip(attributes.voltage)
+                .map(|(r, v)| r as f64 * v as f64 / 1e12),
+        };
+        let health = attributes
+            .capacity
+            .zip(attributes.design_capacity)
+            .filter(|(_, design)| *design > 0)
+            .map(|(c, design)| (100 * c as i64 / design as i64) as u32);
         // When AC uevent fires (AC is plugged or unplugged),
         // sysfs is laggy and still not refreshed by driver/kernel.
         // Pre-shot battery switch on Charging/Discharging state.
@@ -286,48 +827,67 @@ impl Bato {
                 false => PsStatus::Discharging,
             })
             .unwrap_or(sysfs_status);
-        let data = Data {
+        Ok(Data {
+            battery: self.battery.name.clone(),
             current_level: battery_level,
             status,
-        };
-        debug!("update: {}", data);
-        self.fsm.shift(&data);
-        Ok(())
+            rate,
+            power,
+            health,
+        })
+    }
+
+    /// Read the battery once, and the state bato would be in. The state is
+    /// followed without notifying
+    #[instrument(skip(self))]
+    pub fn status(&mut self, uevent_ac: Option<bool>) -> Result<Status> {
+        self.apply_profiles(uevent_ac)
+            .inspect_err(|e| error!("failed to apply profiles: {e}"))
+            .ok();
+        let attributes = self.parse_attributes().context("parse attribute")?;
+        let data = self.data(&attributes, uevent_ac)?;
+        let state = self.fsm.settle(&data);
+        self.fsm.restore(state);
+        Ok(self.status_of(&data, &attributes, uevent_ac))
+    }
+
+    // the status of the battery, in the current state
+    fn status_of(&self, data: &Data, attributes: &Attributes, uevent_ac: Option<bool>) -> Status {
+        let ac = uevent_ac.or_else(|| {
+            read_ac_online()
+                .inspect_err(|e| warn!("failed to read AC state: {e}"))
+                .ok()
+                .flatten()
+        });
+        Status {
+            battery: data.battery.clone(),
+            level: data.current_level,
+            status: data.status,
+            ac,
+            time_remaining: data.time_remaining().map(|d| d.as_secs()),
+            power_w: data.power,
+            health: data.health,
+            temperature: attributes.temperature.map(|t| t as f64 / 10.),
+            state: *self.fsm.current_state(),
+        }
     }
 }
 
 #[instrument]
-fn find_attribute_prefix<'b>(path: &str) -> Result<&'b str> {
+fn find_attribute_prefix(path: &str) -> Result<&'static str> {
     let content = fs::read_to_string(path)?;
-    let unit = if content.contains(&format!(
-        "{}_{}_{}=",
-        POWER_SUPPLY, ENERGY_PREFIX, FULL_DESIGN_ATTRIBUTE
-    )) && content.contains(&format!(
-        "{}_{}_{}=",
-        POWER_SUPPLY, ENERGY_PREFIX, FULL_ATTRIBUTE
-    )) && content.contains(&format!(
-        "{}_{}_{}=",
-        POWER_SUPPLY, ENERGY_PREFIX, NOW_ATTRIBUTE
-    )) {
-        debug!("found attribute prefix {ENERGY_PREFIX}");
-        Some(ENERGY_PREFIX)
-    } else if content.contains(&format!(
-        "{}_{}_{}=",
-        POWER_SUPPLY, CHARGE_PREFIX, FULL_DESIGN_ATTRIBUTE
-    )) && content.contains(&format!(
-        "{}_{}_{}=",
-        POWER_SUPPLY, CHARGE_PREFIX, FULL_ATTRIBUTE
-    )) && content.contains(&format!(
-        "{}_{}_{}=",
-        POWER_SUPPLY, CHARGE_PREFIX, NOW_ATTRIBUTE
-    )) {
-        debug!("found attribute prefix {CHARGE_PREFIX}");
-        Some(CHARGE_PREFIX)
-    } else {
-        None
-    };
-    unit.ok_or_else(|| {
+    attribute_prefix(&content).ok_or_else(|| {
         error!("unable to find attribute in {path}");
         anyhow!("unable to find attribute in {path}")
     })
 }
+
+// the prefix of the battery attributes in the content of an uevent file,
+// `ENERGY` or `CHARGE` depending on the unit the driver reports
+fn attribute_prefix(content: &str) -> Option<&'static str> {
+    [ENERGY_PREFIX, CHARGE_PREFIX].into_iter().find(|prefix| {
+        [FULL_DESIGN_ATTRIBUTE, FULL_ATTRIBUTE, NOW_ATTRIBUTE]
+            .iter()
+            .all(|attribute| content.contains(&format!("{POWER_SUPPLY}_{prefix}_{attribute}=")))
+    })
+}
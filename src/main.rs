@@ -3,9 +3,10 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use anyhow::{Context, Result};
-use bato::{Bato, Config, cli::Cli, signal, trace};
+use bato::cli::{Cli, Command, ConfigCommand};
+use bato::{Bato, Config, signal, trace};
 use clap::Parser;
-use std::time::Duration;
+use std::process::exit;
 use tracing::{debug, instrument, trace};
 
 #[instrument]
@@ -14,16 +15,74 @@ fn main() -> Result<()> {
     let _g = trace::init(&cli).context("failed to init tracing")?;
     debug!("{:?}", cli);
 
+    if let Some(command) = cli.command {
+        return match command {
+            Command::ServerInfo => {
+                let server = bato::probe_server().context("no notification server")?;
+                println!("{server}");
+                Ok(())
+            }
+            Command::PrintDefaultConfig => {
+                print!("{}", bato::reference_config());
+                Ok(())
+            }
+            Command::CheckConfig { file } => {
+                if !bato::check_config(file.or(cli.config))? {
+                    exit(1);
+                }
+                Ok(())
+            }
+            Command::Status { json } => {
+                let config = Config::new(cli.config, cli.overrides)?;
+                let status = Bato::with_config(config)?.status(None)?;
+                match json {
+                    true => println!("{}", serde_json::to_string(&status)?),
+                    false => print!("{status}"),
+                }
+                if status.is_critical() {
+                    exit(2);
+                }
+                Ok(())
+            }
+            Command::Bar { format } => {
+                let config = Config::new(cli.config, cli.overrides)?;
+                bato::bar(config, format)
+            }
+            Command::List { verbose } => {
+                let config = Config::new(cli.config, cli.overrides)?;
+                bato::list_power_supplies(&config, verbose)
+            }
+            Command::TestNotify { target } => {
+                let config = Config::new(cli.config, cli.overrides)?;
+                if !bato::test_notify(config, target)? {
+                    exit(1);
+                }
+                Ok(())
+            }
+            Command::Init => bato::init(cli.config),
+            Command::Config { command } => {
+                let ok = match command {
+                    ConfigCommand::Get { key } => bato::config_get(cli.config, &key)?,
+                    ConfigCommand::Set { key, value } => {
+                        bato::config_set(cli.config, &key, &value)?
+                    }
+                };
+                if !ok {
+                    exit(1);
+                }
+                Ok(())
+            }
+        };
+    }
+
     signal::catch_signals()?;
 
-    let config = Config::new(cli.config)?;
+    let config = Config::new(cli.config, cli.overrides)?;
     trace!("{:#?}", config);
-    debug!("tick rate {}s", config.tick_rate);
-    let tick = Duration::from_secs(config.tick_rate as u64);
     let mut bato = Bato::with_config(config)?;
     debug!("{:#?}", bato);
 
-    bato.run(tick)?;
+    bato.run()?;
 
     Ok(())
 }
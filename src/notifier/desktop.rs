@@ -0,0 +1,231 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, instrument, trace};
+
+use super::queue::{self, OwnedMessage};
+use super::server::{self, ACTIONS, BODY_MARKUP, PERSISTENCE};
+use super::{Message, Notifier};
+use crate::config::{Backend, Notification, Urgency};
+use crate::fsm::State;
+use crate::{Config, action};
+
+// The last notification shown, replaced in place by the next one
+static SHOWN: Lazy<Mutex<Option<Shown>>> = Lazy::new(|| Mutex::new(None));
+
+// delay between two repeats of a critical notification, when the server
+// does not keep notifications until they are dismissed
+const REPEAT_INTERVAL: Duration = Duration::from_secs(300);
+
+#[derive(Debug)]
+struct Shown {
+    state: Option<State>,
+    message: OwnedMessage,
+    handle: notify_rust::NotificationHandle,
+    at: Instant,
+    // shown again every `REPEAT_INTERVAL`
+    repeat: bool,
+    // its state exited, to be closed unless replaced in the meantime
+    expired: bool,
+}
+
+/// Notifications shown by the freedesktop notification server, over D-Bus
+#[derive(Debug)]
+pub struct DesktopNotifier<'a>(pub &'a Config);
+
+/// Mark the notification of a state as expired when leaving the state,
+/// it is closed by `close_expired` unless the next state replaces it
+#[instrument(skip(to_close))]
+pub fn expire(state: State, to_close: &Notification) {
+    if !to_close.close_on_exit.unwrap_or(true) {
+        return;
+    }
+    let mut shown = SHOWN.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(shown) = shown.as_mut().filter(|s| s.state == Some(state)) {
+        trace!("notification expired");
+        shown.expired = true;
+    }
+}
+
+/// Close the last notification shown if its state exited
+#[instrument]
+pub fn close_expired() {
+    let mut shown = SHOWN.lock().unwrap_or_else(|e| e.into_inner());
+    if shown.as_ref().is_some_and(|s| s.expired) {
+        let handle = shown.take().unwrap().handle;
+        debug!("close notification {}", handle.id());
+        handle.close();
+    }
+}
+
+/// The notifications shown are lost when the server restarts, queue the
+/// last one again if critical
+#[instrument]
+pub fn server_restarted() {
+    let Some(shown) = SHOWN.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+        return;
+    };
+    if !shown.expired && shown.message.notification.urgency == Some(Urgency::Critical) {
+        debug!("show critical notification again");
+        queue::requeue(Backend::Desktop, shown.message);
+    }
+}
+
+/// When the last notification shown is to be repeated
+pub fn repeat_due() -> Option<Instant> {
+    let shown = SHOWN.lock().unwrap_or_else(|e| e.into_inner());
+    shown
+        .as_ref()
+        .filter(|s| s.repeat && !s.expired)
+        .map(|s| s.at + REPEAT_INTERVAL)
+}
+
+/// Show the last notification again if due, the server does not persist it
+#[instrument(skip_all)]
+pub fn repeat(config: &Config) {
+    if repeat_due().is_none_or(|due| due > Instant::now()) {
+        return;
+    }
+    let Some(message) = SHOWN
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map(|s| s.message.clone())
+    else {
+        return;
+    };
+    debug!("repeat critical notification");
+    DesktopNotifier(config)
+        .send(&message.message())
+        .inspect_err(|e| error!("failed to repeat notification: {e}"))
+        .ok();
+}
+
+impl Notifier for DesktopNotifier<'_> {
+    #[instrument(skip_all)]
+    fn send(&self, message: &Message) -> Result<()> {
+        let config = self.0;
+        let (state, level, to_send) = (message.state, message.level, message.notification);
+        let server = server::get();
+        let supports = |capability| server.as_ref().is_none_or(|s| s.has(capability));
+        let mut ntf = notify_rust::Notification::new()
+            .summary(&to_send.summary)
+            .finalize();
+        if let Some(body) = to_send.body.as_ref() {
+            match supports(BODY_MARKUP) {
+                true => ntf.body(body),
+                false => ntf.body(&strip_markup(body)),
+            };
+        }
+        if let Some(icon) = to_send.icon.as_ref() {
+            ntf.icon(icon);
+        }
+        if let Some(urgency) = to_send.urgency.as_ref() {
+            ntf.urgency(notify_rust::Urgency::from(urgency));
+        }
+        set_hints(&mut ntf, level, to_send);
+        let mut shown = SHOWN.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(last) = shown.as_ref().filter(|s| config.replace || s.expired) {
+            trace!("replace notification {}", last.handle.id());
+            ntf.id(last.handle.id());
+        }
+        let actions = match supports(ACTIONS) {
+            true => to_send.actions.as_deref().unwrap_or_default(),
+            false => &[],
+        };
+        for action in actions {
+            ntf.action(action.as_ref(), &action::label(config, *action));
+        }
+        debug!("notify show");
+        let handle = ntf
+            .show()
+            .inspect_err(|e| error!("failed to show notification: {e}"))?;
+        if !actions.is_empty() {
+            action::listen(config, handle.id(), state)
+                .inspect_err(|e| error!("failed to listen to actions: {e}"))
+                .ok();
+        }
+        *shown = Some(Shown {
+            state,
+            message: message.into(),
+            handle,
+            at: Instant::now(),
+            repeat: to_send.urgency == Some(Urgency::Critical) && !supports(PERSISTENCE),
+            expired: false,
+        });
+        Ok(())
+    }
+}
+
+// set the optional hints and properties of a notification
+fn set_hints(ntf: &mut notify_rust::Notification, level: Option<u32>, to_send: &Notification) {
+    use notify_rust::Hint;
+    if let Some(level) = level.filter(|_| to_send.value == Some(true)) {
+        ntf.hint(Hint::CustomInt("value".into(), level as i32));
+    }
+    if let Some(category) = to_send.category.as_ref() {
+        ntf.hint(Hint::Category(category.clone()));
+    }
+    if let Some(tag) = to_send.stack_tag.as_ref() {
+        ntf.hint(Hint::Custom("x-dunst-stack-tag".into(), tag.clone()));
+    }
+    if let Some(tag) = to_send.synchronous.as_ref() {
+        ntf.hint(Hint::Custom(
+            "x-canonical-private-synchronous".into(),
+            tag.clone(),
+        ));
+    }
+    if let Some(timeout) = to_send.expire_timeout {
+        ntf.timeout(match timeout {
+            0 => notify_rust::Timeout::Never,
+            ms => notify_rust::Timeout::Milliseconds(ms),
+        });
+    }
+    if let Some(transient) = to_send.transient {
+        ntf.hint(Hint::Transient(transient));
+    }
+    if let Some(resident) = to_send.resident {
+        ntf.hint(Hint::Resident(resident));
+    }
+    if let Some(name) = to_send.sound_name.as_ref() {
+        ntf.hint(Hint::SoundName(name.clone()));
+    }
+    if let Some(file) = to_send.sound_file.as_ref() {
+        ntf.hint(Hint::SoundFile(file.clone()));
+    }
+    if let Some(name) = to_send.app_name.as_ref() {
+        ntf.appname(name);
+    }
+    if let Some(entry) = to_send.desktop_entry.as_ref() {
+        ntf.hint(Hint::DesktopEntry(entry.clone()));
+    }
+}
+
+// strip the tags and unescape the entities of a body, for servers that
+// would show the markup verbatim
+fn strip_markup(body: &str) -> String {
+    let mut text = String::with_capacity(body.len());
+    let mut in_tag = false;
+    for c in body.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    [
+        ("&lt;", "<"),
+        ("&gt;", ">"),
+        ("&quot;", "\""),
+        ("&apos;", "'"),
+        ("&amp;", "&"),
+    ]
+    .iter()
+    .fold(text, |text, (entity, c)| text.replace(entity, c))
+}
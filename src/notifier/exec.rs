@@ -0,0 +1,45 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::{Result, bail};
+use std::process::Command;
+use tracing::{debug, instrument};
+
+use super::{Message, Notifier, wait};
+use crate::config::ExecBackend;
+
+/// Notifications passed to a command, through `BATO_*` environment variables
+#[derive(Debug)]
+pub struct ExecNotifier<'a>(pub &'a ExecBackend);
+
+impl Notifier for ExecNotifier<'_> {
+    #[instrument(skip_all)]
+    fn send(&self, message: &Message) -> Result<()> {
+        let Some((program, args)) = self.0.command.split_first() else {
+            bail!("empty exec command");
+        };
+        let payload = message.payload();
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .env("BATO_SUMMARY", payload.summary)
+            .env("BATO_BODY", payload.body.unwrap_or_default())
+            .env("BATO_ICON", payload.icon.unwrap_or_default());
+        if let Some(state) = payload.state {
+            command.env("BATO_STATE", state.to_string());
+        }
+        if let Some(level) = payload.level {
+            command.env("BATO_LEVEL", level.to_string());
+        }
+        if let Some(urgency) = payload.urgency {
+            command.env("BATO_URGENCY", urgency.as_ref());
+        }
+        debug!("exec {program}");
+        let status = wait(command.spawn()?, program)?;
+        if !status.success() {
+            bail!("{program} exited with {status}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+mod desktop;
+mod exec;
+mod queue;
+mod server;
+mod stdout;
+mod wall;
+mod watch;
+mod webhook;
+mod worker;
+
+use anyhow::{Result, anyhow, bail};
+use serde::Serialize;
+use std::fmt::Debug;
+use std::process::{Child, ExitStatus};
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, instrument};
+
+use crate::Config;
+use crate::config::{Backend, Notification, Urgency};
+use crate::fsm::State;
+
+pub use desktop::close_expired;
+pub use server::{Server, probe as probe_server};
+pub use watch::watch_server;
+pub use worker::start as start_worker;
+
+// longest run of an exec or wall command
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(10);
+// delay between two checks of a running command
+const COMMAND_POLL: Duration = Duration::from_millis(50);
+
+/// A notification ready to be sent, its templates rendered
+#[derive(Debug)]
+pub struct Message<'a> {
+    pub state: Option<State>,
+    pub level: Option<u32>,
+    pub notification: &'a Notification,
+}
+
+/// The message as sent by the backends outside the desktop
+#[derive(Debug, Serialize)]
+pub struct Payload<'a> {
+    pub state: Option<State>,
+    pub level: Option<u32>,
+    pub summary: &'a str,
+    pub body: Option<&'a str>,
+    pub icon: Option<&'a str>,
+    pub urgency: Option<Urgency>,
+}
+
+impl Message<'_> {
+    pub fn payload(&self) -> Payload<'_> {
+        Payload {
+            state: self.state,
+            level: self.level,
+            summary: &self.notification.summary,
+            body: self.notification.body.as_deref(),
+            icon: self.notification.icon.as_deref(),
+            urgency: self.notification.urgency,
+        }
+    }
+
+    /// The message as one line of text
+    pub fn text(&self) -> String {
+        match self.notification.body.as_ref() {
+            Some(body) => format!("{}: {}", self.notification.summary, body),
+            None => self.notification.summary.clone(),
+        }
+    }
+}
+
+/// Leave the state of a notification: its queued messages are dropped,
+/// they would show a state that no longer holds, and its desktop
+/// notification is marked as expired
+pub fn expire(state: State, to_close: &Notification) {
+    queue::drop_state(state);
+    desktop::expire(state, to_close);
+}
+
+/// When the next queued or repeated message is due
+pub fn next_due() -> Option<Instant> {
+    [queue::next_due(), desktop::repeat_due()]
+        .into_iter()
+        .flatten()
+        .min()
+}
+
+/// Send the due queued and repeated messages
+pub fn retry(config: &Config) {
+    queue::retry(config);
+    desktop::repeat(config);
+}
+
+/// A way to deliver notifications
+pub trait Notifier: Debug {
+    fn send(&self, message: &Message) -> Result<()>;
+}
+
+fn notifier(config: &Config, backend: Backend) -> Result<Box<dyn Notifier + '_>> {
+    let missing = || anyhow!("missing [backend.{}] config", backend.as_ref());
+    let notifier: Box<dyn Notifier> = match backend {
+        Backend::Desktop => Box::new(desktop::DesktopNotifier(config)),
+        Backend::Stdout => Box::new(stdout::StdoutNotifier(
+            config.backend.stdout.clone().unwrap_or_default(),
+        )),
+        Backend::Exec => Box::new(exec::ExecNotifier(
+            config.backend.exec.as_ref().ok_or_else(missing)?,
+        )),
+        Backend::Wall => Box::new(wall::WallNotifier),
+        Backend::Webhook => Box::new(webhook::WebhookNotifier(
+            config.backend.webhook.as_ref().ok_or_else(missing)?,
+        )),
+    };
+    Ok(notifier)
+}
+
+/// Send a message through the backends of its notification, or through
+/// the default ones. Fails only if no backend delivered it, the failed
+/// backends are retried later. Once the worker is started, the backends
+/// that may block are sent from it and count as delivered
+#[instrument(skip_all)]
+pub fn send(config: &Config, message: &Message) -> Result<()> {
+    let backends = message
+        .notification
+        .backends
+        .as_ref()
+        .unwrap_or(&config.backends);
+    if backends.is_empty() {
+        debug!("no backend, notification dropped");
+        return Ok(());
+    }
+    let mut result = Err(anyhow!("no backend configured"));
+    for backend in backends {
+        let backend = *backend;
+        debug!("send through {}", backend.as_ref());
+        if blocks(backend) && worker::is_running() {
+            let config = config.clone();
+            let message = queue::OwnedMessage::from(message);
+            worker::run(move || {
+                deliver(&config, backend, &message.message()).ok();
+            });
+            result = Ok(());
+            continue;
+        }
+        match deliver(config, backend, message) {
+            Ok(()) => result = Ok(()),
+            Err(e) if result.is_err() => result = Err(e),
+            Err(_) => {}
+        }
+    }
+    result
+}
+
+// send through a backend, queueing the message on failure
+fn deliver(config: &Config, backend: Backend, message: &Message) -> Result<()> {
+    notifier(config, backend)
+        .and_then(|n| n.send(message))
+        .inspect_err(|e| {
+            error!("failed to notify through {}: {e}", backend.as_ref());
+            queue::push(config, backend, message.into());
+            // the retry may be due before the main-loop wakes up
+            crate::wake();
+        })
+}
+
+/// Whether a backend may hold the thread sending through it for long
+fn blocks(backend: Backend) -> bool {
+    matches!(backend, Backend::Exec | Backend::Wall | Backend::Webhook)
+}
+
+/// Wait for a command, killed once it runs for longer than
+/// `COMMAND_TIMEOUT`
+fn wait(mut child: Child, program: &str) -> Result<ExitStatus> {
+    let deadline = Instant::now() + COMMAND_TIMEOUT;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if Instant::now() >= deadline {
+            child.kill().ok();
+            child.wait().ok();
+            bail!("{program} killed after {}s", COMMAND_TIMEOUT.as_secs());
+        }
+        thread::sleep(COMMAND_POLL);
+    }
+}
@@ -0,0 +1,177 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, instrument, warn};
+
+use super::{Message, blocks, notifier, worker};
+use crate::Config;
+use crate::config::{Backend, Notification};
+use crate::fsm::State;
+
+// longest delay between two attempts, in second
+const MAX_INTERVAL: u64 = 600;
+
+// Notifications that failed to be sent, waiting for a retry
+static QUEUE: Lazy<Mutex<Vec<Pending>>> = Lazy::new(|| Mutex::new(vec![]));
+
+/// A message owning its notification, to be sent later
+#[derive(Debug, Clone)]
+pub struct OwnedMessage {
+    pub state: Option<State>,
+    pub level: Option<u32>,
+    pub notification: Notification,
+}
+
+#[derive(Debug)]
+struct Pending {
+    backend: Backend,
+    message: OwnedMessage,
+    attempts: u32,
+    due: Instant,
+}
+
+impl OwnedMessage {
+    pub fn message(&self) -> Message<'_> {
+        Message {
+            state: self.state,
+            level: self.level,
+            notification: &self.notification,
+        }
+    }
+}
+
+impl From<&Message<'_>> for OwnedMessage {
+    fn from(message: &Message) -> Self {
+        OwnedMessage {
+            state: message.state,
+            level: message.level,
+            notification: message.notification.clone(),
+        }
+    }
+}
+
+fn queue() -> std::sync::MutexGuard<'static, Vec<Pending>> {
+    QUEUE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn delay(config: &Config, attempts: u32) -> Duration {
+    let secs = (config.retry.interval as u64).saturating_mul(1 << attempts.min(16));
+    Duration::from_secs(secs.min(MAX_INTERVAL))
+}
+
+/// Queue a message that failed to be sent through a backend
+#[instrument(skip(config, message))]
+pub fn push(config: &Config, backend: Backend, message: OwnedMessage) {
+    if config.retry.max_attempts == 0 {
+        return;
+    }
+    let mut queue = queue();
+    // a newer notification of the same state supersedes the queued one
+    queue.retain(|p| p.backend != backend || p.message.state != message.state);
+    debug!("queue notification, {} pending", queue.len() + 1);
+    queue.push(Pending {
+        backend,
+        message,
+        attempts: 0,
+        due: Instant::now() + delay(config, 0),
+    });
+}
+
+/// Queue a message that was already delivered, to send it again
+pub fn requeue(backend: Backend, message: OwnedMessage) {
+    queue().push(Pending {
+        backend,
+        message,
+        attempts: 0,
+        due: Instant::now(),
+    });
+}
+
+/// Drop the messages of a state, when leaving it
+pub fn drop_state(state: State) {
+    let mut queue = queue();
+    let len = queue.len();
+    queue.retain(|p| p.message.state != Some(state));
+    if queue.len() < len {
+        debug!("{} queued {state} notification dropped", len - queue.len());
+    }
+}
+
+/// Make the messages queued for a backend due now
+pub fn retry_now(backend: Backend) {
+    let now = Instant::now();
+    queue()
+        .iter_mut()
+        .filter(|p| p.backend == backend)
+        .for_each(|p| p.due = now);
+}
+
+/// When the next queued message is due
+pub fn next_due() -> Option<Instant> {
+    queue().iter().map(|p| p.due).min()
+}
+
+/// Retry the due messages. After `max_attempts` failures a message is
+/// sent through the fallback backends, then dropped
+#[instrument(skip_all)]
+pub fn retry(config: &Config) {
+    let now = Instant::now();
+    let due: Vec<Pending> = {
+        let mut queue = queue();
+        let (due, pending) = queue.drain(..).partition(|p| p.due <= now);
+        *queue = pending;
+        due
+    };
+    for pending in due {
+        match blocks(pending.backend) {
+            true => {
+                let config = config.clone();
+                worker::run(move || attempt(&config, pending));
+            }
+            false => attempt(config, pending),
+        }
+    }
+}
+
+fn attempt(config: &Config, mut pending: Pending) {
+    let message = pending.message.message();
+    pending.attempts += 1;
+    debug!(
+        "retry through {}, attempt #{}",
+        pending.backend.as_ref(),
+        pending.attempts
+    );
+    let result = notifier(config, pending.backend).and_then(|n| n.send(&message));
+    match result {
+        Ok(()) => info!("notification sent through {}", pending.backend.as_ref()),
+        Err(e) if pending.attempts < config.retry.max_attempts => {
+            warn!("retry failed: {e}");
+            pending.due = Instant::now() + delay(config, pending.attempts);
+            queue().push(pending);
+            // sent from the worker, the main-loop may sleep past the delay
+            crate::wake();
+        }
+        Err(e) => {
+            error!("giving up after {} attempts: {e}", pending.attempts);
+            for &backend in &config.retry.fallback {
+                let (config, message) = (config.clone(), pending.message.clone());
+                let send = move || {
+                    notifier(&config, backend)
+                        .and_then(|n| n.send(&message.message()))
+                        .inspect_err(|e| {
+                            error!("failed to notify through {}: {e}", backend.as_ref())
+                        })
+                        .ok();
+                };
+                match blocks(backend) {
+                    true => worker::run(send),
+                    false => send(),
+                }
+            }
+        }
+    }
+}
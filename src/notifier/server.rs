@@ -0,0 +1,99 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use std::fmt::{self, Display};
+use std::sync::Mutex;
+use tracing::{info, instrument, warn};
+
+pub const ACTIONS: &str = "actions";
+pub const BODY_MARKUP: &str = "body-markup";
+pub const PERSISTENCE: &str = "persistence";
+
+// The notification server probed, forgotten when it restarts
+static SERVER: Lazy<Mutex<Option<Server>>> = Lazy::new(|| Mutex::new(None));
+
+/// The notification server, as reported by `GetServerInformation` and
+/// `GetCapabilities`
+#[derive(Debug, Clone)]
+pub struct Server {
+    pub name: String,
+    pub vendor: String,
+    pub version: String,
+    pub spec_version: String,
+    pub capabilities: Vec<String>,
+}
+
+impl Server {
+    pub fn has(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
+
+    // how bato adapts to the missing capabilities
+    fn adaptations(&self) -> Vec<&'static str> {
+        [
+            (BODY_MARKUP, "markup is stripped from the body"),
+            (ACTIONS, "action buttons are omitted"),
+            (PERSISTENCE, "critical notifications are repeated"),
+        ]
+        .into_iter()
+        .filter(|(capability, _)| !self.has(capability))
+        .map(|(_, adaptation)| adaptation)
+        .collect()
+    }
+}
+
+impl Display for Server {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "server:       {} {}", self.name, self.version)?;
+        writeln!(f, "vendor:       {}", self.vendor)?;
+        writeln!(f, "spec version: {}", self.spec_version)?;
+        write!(f, "capabilities: {}", self.capabilities.join(" "))?;
+        for adaptation in self.adaptations() {
+            write!(f, "\n- {adaptation}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Ask the notification server for its information and capabilities
+#[instrument]
+pub fn probe() -> Result<Server> {
+    let information = notify_rust::get_server_information()?;
+    let server = Server {
+        name: information.name,
+        vendor: information.vendor,
+        version: information.version,
+        spec_version: information.spec_version,
+        capabilities: notify_rust::get_capabilities()?,
+    };
+    info!(
+        "notification server {} {}, capabilities: {}",
+        server.name,
+        server.version,
+        server.capabilities.join(" ")
+    );
+    for adaptation in server.adaptations() {
+        info!("{adaptation}");
+    }
+    Ok(server)
+}
+
+/// The notification server, probed on first use. `None` when it can not
+/// be reached, then every capability is assumed
+pub fn get() -> Option<Server> {
+    let mut server = SERVER.lock().unwrap_or_else(|e| e.into_inner());
+    if server.is_none() {
+        *server = probe()
+            .inspect_err(|e| warn!("failed to probe the notification server: {e}"))
+            .ok();
+    }
+    server.clone()
+}
+
+/// Forget the server probed, the next one may differ
+pub fn forget() {
+    *SERVER.lock().unwrap_or_else(|e| e.into_inner()) = None;
+}
@@ -0,0 +1,24 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::Result;
+use std::io::{self, Write};
+
+use super::{Message, Notifier};
+use crate::config::{OutputFormat, StdoutBackend};
+
+/// Notifications printed on stdout, one per line
+#[derive(Debug)]
+pub struct StdoutNotifier(pub StdoutBackend);
+
+impl Notifier for StdoutNotifier {
+    fn send(&self, message: &Message) -> Result<()> {
+        let line = match self.0.format {
+            OutputFormat::Text => message.text(),
+            OutputFormat::Json => serde_json::to_string(&message.payload())?,
+        };
+        writeln!(io::stdout().lock(), "{line}")?;
+        Ok(())
+    }
+}
@@ -0,0 +1,22 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::{Result, bail};
+use std::process::Command;
+
+use super::{Message, Notifier, wait};
+
+/// Notifications broadcast to all the terminals with `wall`
+#[derive(Debug)]
+pub struct WallNotifier;
+
+impl Notifier for WallNotifier {
+    fn send(&self, message: &Message) -> Result<()> {
+        let status = wait(Command::new("wall").arg(message.text()).spawn()?, "wall")?;
+        if !status.success() {
+            bail!("wall exited with {status}");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,46 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::Result;
+use std::thread;
+use tracing::{debug, error, info, instrument};
+use zbus::blocking::{Connection, fdo::DBusProxy};
+
+use super::{desktop, queue, server};
+use crate::config::Backend;
+
+const NOTIFICATIONS_NAME: &str = "org.freedesktop.Notifications";
+
+/// Watch the notification server name on the session bus. When it
+/// appears, the queued notifications are retried right away
+#[instrument]
+pub fn watch_server() -> Result<()> {
+    let connection = Connection::session()?;
+    thread::Builder::new()
+        .name("server_watcher".into())
+        .spawn(move || {
+            let watch = || -> Result<()> {
+                let proxy = DBusProxy::new(&connection)?;
+                for signal in
+                    proxy.receive_name_owner_changed_with_args(&[(0, NOTIFICATIONS_NAME)])?
+                {
+                    let args = signal.args()?;
+                    if args.new_owner().is_none() {
+                        debug!("notification server left the bus");
+                        continue;
+                    }
+                    info!("notification server appeared on the bus");
+                    server::forget();
+                    desktop::server_restarted();
+                    queue::retry_now(Backend::Desktop);
+                    crate::wake();
+                }
+                Ok(())
+            };
+            watch()
+                .inspect_err(|e| error!("failed to watch the notification server: {e}"))
+                .ok();
+        })?;
+    Ok(())
+}
@@ -0,0 +1,32 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::Result;
+use std::time::Duration;
+use tracing::{debug, instrument};
+
+use super::{Message, Notifier};
+use crate::config::{DEFAULT_WEBHOOK_TIMEOUT, WebhookBackend};
+
+/// Notifications POSTed as JSON to an URL
+#[derive(Debug)]
+pub struct WebhookNotifier<'a>(pub &'a WebhookBackend);
+
+impl Notifier for WebhookNotifier<'_> {
+    #[instrument(skip_all)]
+    fn send(&self, message: &Message) -> Result<()> {
+        let timeout = self.0.timeout.unwrap_or(DEFAULT_WEBHOOK_TIMEOUT);
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(timeout as u64)))
+            .build()
+            .into();
+        let body = serde_json::to_string(&message.payload())?;
+        debug!("POST {}", self.0.url);
+        agent
+            .post(&self.0.url)
+            .header("Content-Type", "application/json")
+            .send(body)?;
+        Ok(())
+    }
+}
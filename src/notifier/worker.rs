@@ -0,0 +1,50 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::{Result, anyhow};
+use once_cell::sync::OnceCell;
+use std::sync::Mutex;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use tracing::{debug, error, instrument};
+
+type Job = Box<dyn FnOnce() + Send>;
+
+// The jobs of the worker thread, once started
+static WORKER: OnceCell<Mutex<Sender<Job>>> = OnceCell::new();
+
+/// Start the thread sending through the backends that may block, so they
+/// do not hold the main-loop. The jobs run one after the other, in order
+#[instrument]
+pub fn start() -> Result<()> {
+    let (sender, receiver) = mpsc::channel::<Job>();
+    thread::Builder::new()
+        .name("notifier_worker".into())
+        .spawn(move || {
+            for job in receiver {
+                job();
+            }
+            debug!("notifier worker stopped");
+        })?;
+    WORKER
+        .set(Mutex::new(sender))
+        .map_err(|_| anyhow!("notifier worker already started"))
+}
+
+pub fn is_running() -> bool {
+    WORKER.get().is_some()
+}
+
+/// Run a job on the worker thread, or right away when it is not started
+pub fn run(job: impl FnOnce() + Send + 'static) {
+    let Some(worker) = WORKER.get() else {
+        job();
+        return;
+    };
+    let sender = worker.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = sender.send(Box::new(job)) {
+        error!("notifier worker is gone, running the job here");
+        (e.0)();
+    }
+}
@@ -0,0 +1,95 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{env, fs};
+use tracing::{debug, error, instrument, trace};
+
+use crate::fsm::{PsStatus, State};
+use crate::history::History;
+use crate::{APP_DIR, util};
+
+const XDG_STATE_HOME: &str = "XDG_STATE_HOME";
+const STATE_FILE: &str = "state.json";
+
+// Set by another thread to have the main-loop save the state
+static SAVE: AtomicBool = AtomicBool::new(false);
+
+/// What identifies a battery across restarts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatteryId {
+    pub name: String,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+}
+
+/// The state persisted in `$XDG_STATE_HOME/bato/state.json`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub battery: BatteryId,
+    pub state: State,
+    pub status: PsStatus,
+    pub history: History,
+}
+
+/// Ask the main-loop to save the state, eg. after a change of the history
+/// made by a notification action
+pub fn request() {
+    SAVE.store(true, Ordering::Relaxed);
+    crate::wake();
+}
+
+/// Whether a save was requested since the last call
+pub fn requested() -> bool {
+    SAVE.swap(false, Ordering::Relaxed)
+}
+
+fn state_dir() -> Result<PathBuf> {
+    let home = env::var("HOME")?;
+    let state_dir = env::var(XDG_STATE_HOME)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| Path::new(&home).join(".local/state"));
+    Ok(state_dir.join(APP_DIR))
+}
+
+#[instrument]
+pub fn load() -> Result<Option<Snapshot>> {
+    let file = state_dir()?.join(STATE_FILE);
+    let content = match fs::read_to_string(&file) {
+        Ok(c) => c,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            debug!("no state file {}", file.display());
+            return Ok(None);
+        }
+        Err(e) => {
+            error!("failed to read state file {}: {e}", file.display());
+            return Err(e.into());
+        }
+    };
+    let snapshot = serde_json::from_str(&content)
+        .inspect_err(|e| error!("failed to parse state file {}: {e}", file.display()))
+        .context(format!("failed to parse state file {}", file.display()))?;
+    Ok(Some(snapshot))
+}
+
+#[instrument(skip_all)]
+pub fn save(snapshot: &Snapshot) -> Result<()> {
+    let dir = state_dir()?;
+    util::check_dir_or_create(&dir)?;
+    let file = dir.join(STATE_FILE);
+    let tmp_file = file.with_extension("json.tmp");
+    let content = serde_json::to_string_pretty(snapshot)?;
+    // write then rename, so a crash never leaves a truncated state file
+    fs::write(&tmp_file, content)
+        .and_then(|_| fs::rename(&tmp_file, &file))
+        .inspect_err(|e| error!("failed to write state file {}: {e}", file.display()))
+        .context(format!("failed to write state file {}", file.display()))?;
+    trace!("state saved to {}", file.display());
+    Ok(())
+}
@@ -0,0 +1,157 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::Result;
+use tracing::{instrument, warn};
+
+use crate::cli::NotifyTarget;
+use crate::config::Notification;
+use crate::fsm::{Data, PsStatus, State};
+use crate::{Bato, Config, profile, quiet};
+
+// fake values, when there is no battery to read
+const FAKE_BATTERY: &str = "BAT0";
+const FAKE_LEVEL: u32 = 50;
+const FAKE_RATE: f64 = 10.;
+const FAKE_POWER: f64 = 10.;
+const FAKE_HEALTH: u32 = 90;
+
+/// Send the notifications of the target states through the regular path,
+/// rendered with the current battery values, or fake ones without battery.
+/// The level and status are adjusted to the state. Returns false if a
+/// notification failed
+#[instrument(skip(config))]
+pub fn test_notify(config: Config, target: NotifyTarget) -> Result<bool> {
+    let (mut config, current) = match Bato::with_config(config.clone()) {
+        Ok(mut bato) => {
+            bato.apply_profiles(None)
+                .inspect_err(|e| warn!("failed to apply profiles: {e}"))
+                .ok();
+            let data = bato
+                .parse_attributes()
+                .and_then(|attributes| bato.data(&attributes, None))
+                .inspect_err(|e| warn!("failed to read the battery: {e}"))
+                .ok();
+            (bato.config, data)
+        }
+        Err(_) => {
+            let profiles = profile::active(&config, None);
+            (config.profiled(&profiles)?, None)
+        }
+    };
+    if current.is_none() {
+        println!("no battery to read, using fake values");
+    }
+    let states = match target {
+        NotifyTarget::Charging => vec![State::Charging],
+        NotifyTarget::Discharging => vec![State::Discharging],
+        NotifyTarget::Full => vec![State::Full],
+        NotifyTarget::Low => vec![State::Low],
+        NotifyTarget::Critical => vec![State::Critical],
+        NotifyTarget::All => {
+            // one popup each, instead of the last one only
+            config.replace = false;
+            vec![
+                State::Charging,
+                State::Discharging,
+                State::Full,
+                State::Low,
+                State::Critical,
+            ]
+        }
+    };
+
+    let mut ok = true;
+    for state in states {
+        let name = section(state);
+        let Some(notification) = notification(&config, state) else {
+            println!("{name}: disabled, no [{name}] section");
+            continue;
+        };
+        if state != State::Critical
+            && let Some(mode) = quiet::active_mode(&config)
+        {
+            println!("{name}: held back by quiet hours ({})", mode.as_ref());
+            continue;
+        }
+        if notification
+            .backends
+            .as_ref()
+            .unwrap_or(&config.backends)
+            .is_empty()
+        {
+            println!("{name}: silenced, no backend");
+            continue;
+        }
+        let data = sample(&config, state, current.as_ref());
+        match crate::notify(&config, state, previous(state), &data, notification) {
+            Ok(()) => println!("{name}: sent"),
+            Err(e) => {
+                println!("{name}: failed, {e}");
+                ok = false;
+            }
+        }
+    }
+    Ok(ok)
+}
+
+fn notification(config: &Config, state: State) -> Option<&Notification> {
+    match state {
+        State::Charging => config.charging.as_ref(),
+        State::Discharging => config.discharging.as_ref(),
+        State::Full => config.full.as_ref(),
+        State::Low => config.low.as_ref(),
+        State::Critical => config.critical.as_ref(),
+        State::NotCharging => None,
+    }
+}
+
+// the config section of the notification of a state
+fn section(state: State) -> &'static str {
+    match state {
+        State::Charging => "charging",
+        State::Discharging => "discharging",
+        State::Full => "full",
+        State::Low => "low",
+        State::Critical => "critical",
+        State::NotCharging => "not_charging",
+    }
+}
+
+// the state usually left when entering a state
+fn previous(state: State) -> State {
+    match state {
+        State::Discharging | State::Full => State::Charging,
+        State::Critical => State::Low,
+        _ => State::Discharging,
+    }
+}
+
+// data in which the state holds, from the current data if any
+fn sample(config: &Config, state: State, current: Option<&Data>) -> Data {
+    let data = current.cloned().unwrap_or_else(|| Data {
+        battery: FAKE_BATTERY.to_string(),
+        current_level: FAKE_LEVEL,
+        status: PsStatus::Discharging,
+        rate: Some(FAKE_RATE),
+        power: Some(FAKE_POWER),
+        health: Some(FAKE_HEALTH),
+    });
+    // a level between the thresholds for charging and discharging
+    let between = data
+        .current_level
+        .clamp(config.low_level + 1, 99.max(config.low_level + 1));
+    let (current_level, status) = match state {
+        State::Charging => (between, PsStatus::Charging),
+        State::Full => (100, PsStatus::Full),
+        State::Low => (config.low_level, PsStatus::Discharging),
+        State::Critical => (config.critical_level, PsStatus::Discharging),
+        _ => (between, PsStatus::Discharging),
+    };
+    Data {
+        current_level,
+        status,
+        ..data
+    }
+}
@@ -0,0 +1,43 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fs;
+use std::path::Path;
+use tracing::{instrument, trace};
+
+use crate::config::Condition;
+use crate::util::{self, LocalTime};
+use crate::{Config, SYS_PATH};
+
+/// The names of the profiles whose conditions hold, in config order
+#[instrument(skip(config))]
+pub fn active(config: &Config, ac: Option<bool>) -> Vec<String> {
+    let now = util::local_time(util::now());
+    config
+        .profiles
+        .iter()
+        .filter(|p| matches(&p.when, ac, &now))
+        .map(|p| p.name.clone())
+        .collect()
+}
+
+fn matches(when: &Condition, ac: Option<bool>, now: &LocalTime) -> bool {
+    when.ac.is_none_or(|expected| ac == Some(expected))
+        && when
+            .time
+            .as_ref()
+            .is_none_or(|ranges| ranges.iter().any(|r| r.contains(now)))
+        && when.device.as_deref().is_none_or(is_online)
+}
+
+// whether a power supply is present, and online if it reports it
+fn is_online(device: &str) -> bool {
+    let path = Path::new(SYS_PATH).join(device);
+    let online = match fs::read_to_string(path.join("online")) {
+        Ok(online) => online.trim() != "0",
+        Err(_) => path.is_dir(),
+    };
+    trace!("device {device} online: {online}");
+    online
+}
@@ -0,0 +1,111 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use tracing::{debug, info, instrument};
+
+use crate::config::{Notification, QuietMode, Urgency};
+use crate::fsm::State;
+use crate::notifier::{self, Message};
+use crate::{Config, util};
+
+// Notifications deferred during quiet hours
+static DEFERRED: Lazy<Mutex<Vec<Deferred>>> = Lazy::new(|| Mutex::new(vec![]));
+
+#[derive(Debug)]
+struct Deferred {
+    state: State,
+    notification: Notification,
+    at: u64,
+}
+
+/// The quiet hours mode, if the current time falls in quiet hours
+pub fn active_mode(config: &Config) -> Option<QuietMode> {
+    let quiet_hours = config.quiet_hours.as_ref()?;
+    let now = util::local_time(util::now());
+    quiet_hours
+        .ranges
+        .iter()
+        .any(|r| r.contains(&now))
+        .then_some(quiet_hours.mode)
+}
+
+#[instrument(skip(notification))]
+pub fn defer(state: State, notification: &Notification) {
+    let mut deferred = DEFERRED.lock().unwrap_or_else(|e| e.into_inner());
+    // only the last notification of a given state matters
+    deferred.retain(|d| d.state != state);
+    deferred.push(Deferred {
+        state,
+        notification: notification.clone(),
+        at: util::now(),
+    });
+    debug!("{} deferred notification(s)", deferred.len());
+}
+
+/// Once quiet hours are over, send one notification summarizing the
+/// deferred ones
+#[instrument(skip_all)]
+pub fn flush(config: &Config) -> Result<()> {
+    if active_mode(config).is_some() {
+        return Ok(());
+    }
+    let deferred: Vec<Deferred> = {
+        let mut deferred = DEFERRED.lock().unwrap_or_else(|e| e.into_inner());
+        if deferred.is_empty() {
+            return Ok(());
+        }
+        deferred.drain(..).collect()
+    };
+    info!(
+        "quiet hours over, {} deferred notification(s)",
+        deferred.len()
+    );
+    let body = deferred
+        .iter()
+        .map(|d| {
+            let minutes = util::local_time(d.at).minutes;
+            let text = d
+                .notification
+                .body
+                .as_ref()
+                .unwrap_or(&d.notification.summary);
+            format!("{:02}:{:02} {text}", minutes / 60, minutes % 60)
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    // the last deferred notification gives the look of the summary. It
+    // belongs to no state: no action, and it expires like a normal one
+    // even when summarizing a critical notification
+    let last = &deferred[deferred.len() - 1].notification;
+    let summary = Notification {
+        summary: last.summary.clone(),
+        body: Some(body),
+        icon: last.icon.clone(),
+        urgency: Some(Urgency::Normal),
+        min_interval: None,
+        close_on_exit: Some(false),
+        actions: None,
+        value: None,
+        category: last.category.clone(),
+        stack_tag: None,
+        synchronous: None,
+        expire_timeout: None,
+        transient: None,
+        resident: None,
+        sound_name: None,
+        sound_file: None,
+        app_name: last.app_name.clone(),
+        desktop_entry: last.desktop_entry.clone(),
+        backends: last.backends.clone(),
+    };
+    let message = Message {
+        state: None,
+        level: None,
+        notification: &summary,
+    };
+    notifier::send(config, &message)
+}
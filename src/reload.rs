@@ -0,0 +1,146 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::{Result, anyhow};
+use mio::event::Source;
+use mio::unix::SourceFd;
+use mio::{Interest, Registry, Token};
+use std::ffi::{CString, OsString};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{io, mem, ptr};
+use tracing::{debug, instrument, trace};
+
+// Set by SIGHUP or a change of the config file, cleared by the main-loop
+static RELOAD: AtomicBool = AtomicBool::new(false);
+
+/// Ask the main-loop to reload the config
+pub fn request() {
+    RELOAD.store(true, Ordering::Relaxed);
+    crate::wake();
+}
+
+/// Whether a reload was requested since the last call
+pub fn requested() -> bool {
+    RELOAD.swap(false, Ordering::Relaxed)
+}
+
+/// Watch the config files with inotify. The parent directory of a file
+/// is watched, as editors often replace the file instead of writing it in
+/// place
+#[derive(Debug)]
+pub struct ConfigWatcher {
+    fd: OwnedFd,
+    // the file name to look for in each watched directory, any toml file
+    // in a drop-in directory
+    watches: Vec<(i32, Option<OsString>)>,
+}
+
+impl ConfigWatcher {
+    /// Watch files and drop-in directories
+    #[instrument]
+    pub fn new(paths: &[PathBuf]) -> Result<Self> {
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        // SAFETY: the fd was just created and is owned by nobody else
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+        let mut watches = vec![];
+        for path in paths {
+            let (dir, file_name) = match path.is_dir() {
+                true => (path.as_path(), None),
+                false => {
+                    let file_name = path
+                        .file_name()
+                        .ok_or_else(|| anyhow!("not a file: {}", path.display()))?;
+                    let dir = match path.parent() {
+                        Some(dir) if !dir.as_os_str().is_empty() => dir,
+                        _ => Path::new("."),
+                    };
+                    (dir, Some(file_name.to_os_string()))
+                }
+            };
+            let c_dir = CString::new(dir.as_os_str().as_bytes())?;
+            let mask = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_DELETE;
+            let wd = unsafe { libc::inotify_add_watch(fd.as_raw_fd(), c_dir.as_ptr(), mask) };
+            if wd < 0 {
+                return Err(io::Error::last_os_error().into());
+            }
+            debug!("watching {}", dir.display());
+            watches.push((wd, file_name));
+        }
+        Ok(ConfigWatcher { fd, watches })
+    }
+
+    /// Drain the pending events, true if one of them is about a config
+    /// file
+    pub fn changed(&self) -> bool {
+        const EVENT_SIZE: usize = mem::size_of::<libc::inotify_event>();
+        let mut buffer = [0u8; 4096];
+        let mut changed = false;
+        loop {
+            let len = unsafe {
+                libc::read(
+                    self.fd.as_raw_fd(),
+                    buffer.as_mut_ptr().cast(),
+                    buffer.len(),
+                )
+            };
+            if len <= 0 {
+                break;
+            }
+            let len = len as usize;
+            let mut offset = 0;
+            while offset + EVENT_SIZE <= len {
+                // SAFETY: the kernel writes whole events in the buffer
+                let event: libc::inotify_event =
+                    unsafe { ptr::read_unaligned(buffer[offset..].as_ptr().cast()) };
+                let name_start = offset + EVENT_SIZE;
+                let name_end = (name_start + event.len as usize).min(len);
+                // the name is padded with nul bytes
+                let name = buffer[name_start..name_end]
+                    .split(|b| *b == 0)
+                    .next()
+                    .unwrap_or_default();
+                trace!("inotify event on {}", String::from_utf8_lossy(name));
+                changed |= self.watches.iter().any(|(wd, file_name)| {
+                    *wd == event.wd
+                        && match file_name {
+                            Some(file_name) => name == file_name.as_bytes(),
+                            None => name.ends_with(b".toml"),
+                        }
+                });
+                offset = name_end;
+            }
+        }
+        changed
+    }
+}
+
+impl Source for ConfigWatcher {
+    fn register(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.fd.as_raw_fd()).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.fd.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        SourceFd(&self.fd.as_raw_fd()).deregister(registry)
+    }
+}
@@ -0,0 +1,134 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use mio::event::Source;
+use mio::unix::SourceFd;
+use mio::{Interest, Registry, Token};
+use std::fs::File;
+use std::io::{self, Read};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::time::Duration;
+use tracing::{instrument, trace};
+
+// below this, a clock drift is not considered as a suspend
+const SUSPEND_THRESHOLD: Duration = Duration::from_secs(2);
+
+/// Detect system resume by watching the gap between `CLOCK_BOOTTIME`,
+/// which keeps counting while the system is suspended, and
+/// `CLOCK_MONOTONIC`, which does not
+#[derive(Debug)]
+pub struct ResumeDetector {
+    offset: Duration,
+}
+
+fn clock(id: libc::clockid_t) -> Duration {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: `ts` is a valid, writable timespec and both clock ids are
+    // supported on Linux, clock_gettime cannot fail
+    unsafe { libc::clock_gettime(id, &mut ts) };
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+fn offset() -> Duration {
+    clock(libc::CLOCK_BOOTTIME).saturating_sub(clock(libc::CLOCK_MONOTONIC))
+}
+
+impl ResumeDetector {
+    pub fn new() -> Self {
+        ResumeDetector { offset: offset() }
+    }
+
+    /// Returns the time spent suspended since the last check, if any
+    #[instrument(skip(self))]
+    pub fn check(&mut self) -> Option<Duration> {
+        let offset = offset();
+        let suspended = offset.saturating_sub(self.offset);
+        self.offset = offset;
+        trace!("suspended for {suspended:?}");
+        (suspended >= SUSPEND_THRESHOLD).then_some(suspended)
+    }
+}
+
+/// A timer on `CLOCK_BOOTTIME`, the tick source of the main-loop. Unlike
+/// a poll timeout, measured on `CLOCK_MONOTONIC`, its time keeps running
+/// while the system is suspended, so it fires right on resume when the
+/// tick fell during the suspend
+#[derive(Debug)]
+pub struct BootTimer {
+    fd: File,
+}
+
+impl BootTimer {
+    pub fn new() -> io::Result<Self> {
+        // SAFETY: plain syscall, the returned fd is checked
+        let fd = unsafe {
+            libc::timerfd_create(libc::CLOCK_BOOTTIME, libc::TFD_NONBLOCK | libc::TFD_CLOEXEC)
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `fd` is a new file descriptor owned by nothing else
+        Ok(BootTimer {
+            fd: unsafe { File::from_raw_fd(fd) },
+        })
+    }
+
+    /// Arm the timer to fire once, after `timeout`
+    pub fn set(&mut self, timeout: Duration) -> io::Result<()> {
+        self.clear();
+        // a zero value would disarm the timer
+        let timeout = timeout.max(Duration::from_nanos(1));
+        let spec = libc::itimerspec {
+            it_interval: libc::timespec {
+                tv_sec: 0,
+                tv_nsec: 0,
+            },
+            it_value: libc::timespec {
+                tv_sec: timeout.as_secs() as libc::time_t,
+                tv_nsec: timeout.subsec_nanos() as libc::c_long,
+            },
+        };
+        // SAFETY: `spec` is a valid itimerspec, the old value is not read
+        let res =
+            unsafe { libc::timerfd_settime(self.fd.as_raw_fd(), 0, &spec, std::ptr::null_mut()) };
+        match res {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    // read the pending expiration, so the next one is a new edge for the
+    // poll
+    fn clear(&mut self) {
+        let mut expirations = [0; 8];
+        self.fd.read_exact(&mut expirations).ok();
+    }
+}
+
+impl Source for BootTimer {
+    fn register(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.fd.as_raw_fd()).register(registry, token, interests)
+    }
+
+    fn reregister(
+        &mut self,
+        registry: &Registry,
+        token: Token,
+        interests: Interest,
+    ) -> io::Result<()> {
+        SourceFd(&self.fd.as_raw_fd()).reregister(registry, token, interests)
+    }
+
+    fn deregister(&mut self, registry: &Registry) -> io::Result<()> {
+        SourceFd(&self.fd.as_raw_fd()).deregister(registry)
+    }
+}
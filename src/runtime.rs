@@ -0,0 +1,66 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::{Context, Result, bail};
+use std::path::PathBuf;
+use std::{env, fs, process};
+use tracing::{debug, instrument};
+
+use crate::{APP_DIR, util};
+
+const XDG_RUNTIME_DIR: &str = "XDG_RUNTIME_DIR";
+const PID_FILE: &str = "bato.pid";
+
+/// The runtime directory of bato, `$XDG_RUNTIME_DIR/bato`
+pub fn runtime_dir() -> PathBuf {
+    env::var(XDG_RUNTIME_DIR)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| env::temp_dir())
+        .join(APP_DIR)
+}
+
+/// Record the pid of the running daemon
+#[instrument]
+pub fn write_pid() -> Result<()> {
+    let dir = runtime_dir();
+    util::check_dir_or_create(&dir)?;
+    let file = dir.join(PID_FILE);
+    fs::write(&file, process::id().to_string())
+        .context(format!("failed to write pid file {}", file.display()))?;
+    debug!("pid file {}", file.display());
+    Ok(())
+}
+
+pub fn remove_pid() {
+    fs::remove_file(runtime_dir().join(PID_FILE)).ok();
+}
+
+/// The pid of the running daemon, if any
+pub fn daemon_pid() -> Option<i32> {
+    let pid: i32 = fs::read_to_string(runtime_dir().join(PID_FILE))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    // a stale pid file may point to a recycled pid
+    let comm = fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+    (comm.trim() == APP_DIR).then_some(pid)
+}
+
+/// Ask the running daemon to reload its config, returns false if it is
+/// not running
+#[instrument]
+pub fn signal_reload() -> Result<bool> {
+    let Some(pid) = daemon_pid() else {
+        return Ok(false);
+    };
+    if unsafe { libc::kill(pid, libc::SIGHUP) } != 0 {
+        bail!(
+            "failed to signal bato ({pid}): {}",
+            std::io::Error::last_os_error()
+        );
+    }
+    debug!("SIGHUP sent to {pid}");
+    Ok(true)
+}
@@ -2,10 +2,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::RUN;
+use crate::{RUN, reload};
 
 use anyhow::Result;
-use signal_hook::consts::{SIGINT, SIGQUIT, SIGTERM};
+use signal_hook::consts::{SIGHUP, SIGINT, SIGQUIT, SIGTERM};
 use signal_hook::iterator::Signals;
 use std::process::exit;
 use std::sync::atomic::Ordering;
@@ -13,7 +13,7 @@ use std::thread;
 use std::time::Duration;
 use tracing::{info, instrument};
 
-const SIGNALS: [i32; 3] = [SIGINT, SIGTERM, SIGQUIT];
+const SIGNALS: [i32; 4] = [SIGINT, SIGTERM, SIGQUIT, SIGHUP];
 const EXIT_TIMEOUT: Duration = Duration::from_millis(500);
 
 #[instrument]
@@ -22,15 +22,22 @@ pub fn catch_signals() -> Result<()> {
     let builder = thread::Builder::new().name("signal_handler".into());
 
     builder.spawn(move || {
-        if let Some(sig) = signals.forever().next() {
+        for sig in signals.forever() {
             match sig {
+                SIGHUP => {
+                    info!("received {sig}:SIGHUP, reloading config");
+                    reload::request();
+                    continue;
+                }
                 SIGINT => info!("received {sig}:SIGINT"),
                 SIGTERM => info!("received {sig}:SIGTERM"),
                 SIGQUIT => info!("received {sig}:SIGQUIT"),
                 _ => {}
             }
             RUN.store(false, Ordering::Relaxed);
-            // wait for the main app thread to close and exit
+            // the main-loop exits through its cleanup, the state is saved
+            // and the runtime files removed
+            crate::wake();
             // if it takes too long force exit
             thread::sleep(EXIT_TIMEOUT);
             info!("force exit");
@@ -0,0 +1,76 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+use crate::fsm::{PsStatus, State};
+use crate::util;
+
+/// A reading of the battery, as printed by `bato status` and sent to
+/// `bato bar`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Status {
+    pub battery: String,
+    /// As a percentage
+    pub level: u32,
+    pub status: PsStatus,
+    /// Whether the AC is online, `None` without AC adapter
+    pub ac: Option<bool>,
+    /// Until empty or full, in second
+    pub time_remaining: Option<u64>,
+    pub power_w: Option<f64>,
+    /// As a percentage of the design capacity
+    pub health: Option<u32>,
+    /// In °C
+    pub temperature: Option<f64>,
+    /// The state bato would be in
+    pub state: State,
+}
+
+impl Status {
+    pub fn is_critical(&self) -> bool {
+        self.state == State::Critical
+    }
+}
+
+// `?` when a value is not available, like in the templates
+fn or_unknown<T>(value: Option<T>, format: impl Fn(T) -> String) -> String {
+    value.map(format).unwrap_or_else(|| "?".to_string())
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let rows = [
+            ("battery", self.battery.clone()),
+            ("level", format!("{}%", self.level)),
+            ("status", self.status.as_ref().to_string()),
+            (
+                "ac",
+                or_unknown(self.ac, |ac| match ac {
+                    true => "online".to_string(),
+                    false => "offline".to_string(),
+                }),
+            ),
+            (
+                "time remaining",
+                or_unknown(self.time_remaining, |t| {
+                    util::format_duration(Duration::from_secs(t))
+                }),
+            ),
+            ("power", or_unknown(self.power_w, |p| format!("{p:.1} W"))),
+            ("health", or_unknown(self.health, |h| format!("{h}%"))),
+            (
+                "temperature",
+                or_unknown(self.temperature, |t| format!("{t:.1} °C")),
+            ),
+            ("state", self.state.to_string()),
+        ];
+        for (name, value) in rows {
+            writeln!(f, "{name:<15} {value}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::Result;
+use std::fs;
+use std::path::Path;
+use tracing::{error, instrument, warn};
+
+use crate::{
+    BATTERY_TYPE, CAPACITY_ATTRIBUTE, CHARGE_PREFIX, Config, FULL_ATTRIBUTE, FULL_DESIGN_ATTRIBUTE,
+    MAINS_TYPE, NOW_ATTRIBUTE, ONLINE_ATTRIBUTE, POWER_SUPPLY, PRESENT_ATTRIBUTE, SCOPE_ATTRIBUTE,
+    STATUS_ATTRIBUTE, SYS_PATH, TYPE_ATTRIBUTE, UEVENT, attribute_prefix, find_battery,
+    uevent_attribute,
+};
+
+const DEVICE_SCOPE: &str = "Device";
+
+/// A power supply of `/sys/class/power_supply/`, as read from its uevent
+/// file
+#[derive(Debug)]
+pub struct PowerSupply {
+    pub name: String,
+    /// Battery, Mains, USB…
+    pub kind: Option<String>,
+    /// System or Device
+    pub scope: Option<String>,
+    pub online: Option<bool>,
+    pub present: Option<bool>,
+    pub status: Option<String>,
+    /// Level, as a percentage
+    pub capacity: Option<u32>,
+    /// `ENERGY` or `CHARGE`, the prefix of the battery attributes
+    pub unit: Option<&'static str>,
+    /// The raw content of the uevent file
+    pub uevent: String,
+}
+
+impl PowerSupply {
+    #[instrument]
+    pub fn read(name: &str) -> Result<Self> {
+        let path = Path::new(SYS_PATH).join(name).join(UEVENT);
+        let uevent = fs::read_to_string(&path)
+            .inspect_err(|e| error!("failed to read {}: {e}", path.display()))?;
+        let attribute = |key| uevent_attribute(&uevent, key).map(String::from);
+        let flag = |key| uevent_attribute(&uevent, key).map(|v| v == "1");
+        Ok(PowerSupply {
+            name: name.to_string(),
+            kind: attribute(TYPE_ATTRIBUTE),
+            scope: attribute(SCOPE_ATTRIBUTE),
+            online: flag(ONLINE_ATTRIBUTE),
+            present: flag(PRESENT_ATTRIBUTE),
+            status: attribute(STATUS_ATTRIBUTE),
+            capacity: uevent_attribute(&uevent, CAPACITY_ATTRIBUTE).and_then(|v| v.parse().ok()),
+            unit: attribute_prefix(&uevent),
+            uevent,
+        })
+    }
+
+    /// A battery of the system, one bato can monitor
+    pub fn is_battery(&self) -> bool {
+        self.kind.as_deref() == Some(BATTERY_TYPE) && !self.is_peripheral()
+    }
+
+    /// A device powered by its own battery, eg. a mouse or a headset
+    pub fn is_peripheral(&self) -> bool {
+        self.scope.as_deref() == Some(DEVICE_SCOPE)
+    }
+
+    /// An AC adapter or a charging port
+    pub fn is_ac(&self) -> bool {
+        !self.is_peripheral()
+            && self
+                .kind
+                .as_deref()
+                .is_some_and(|kind| kind == MAINS_TYPE || kind.starts_with("USB"))
+    }
+
+    /// The attributes bato reads the current and full values from
+    pub fn attributes(&self, full_design: bool) -> Option<(String, String)> {
+        let full = match full_design {
+            true => FULL_DESIGN_ATTRIBUTE,
+            false => FULL_ATTRIBUTE,
+        };
+        self.unit.map(|unit| {
+            (
+                format!("{POWER_SUPPLY}_{unit}_{NOW_ATTRIBUTE}"),
+                format!("{POWER_SUPPLY}_{unit}_{full}"),
+            )
+        })
+    }
+
+    /// The unit of the battery attributes, in words
+    pub fn unit_name(&self) -> Option<&'static str> {
+        self.unit.map(|unit| match unit {
+            CHARGE_PREFIX => "charge (µAh)",
+            _ => "energy (µWh)",
+        })
+    }
+}
+
+/// Every power supply of `/sys/class/power_supply/`, sorted by name
+#[instrument]
+pub fn list() -> Result<Vec<PowerSupply>> {
+    let mut names: Vec<String> = fs::read_dir(SYS_PATH)
+        .inspect_err(|e| error!("failed to read dir {}: {}", SYS_PATH, e))?
+        .filter_map(|entry| {
+            entry
+                .inspect_err(|e| warn!("failed to read entry in {SYS_PATH}: {e}"))
+                .ok()
+        })
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    Ok(names
+        .iter()
+        .filter_map(|name| PowerSupply::read(name).ok())
+        .collect())
+}
+
+/// Print every power supply with its attributes, and the battery bato
+/// uses. With `verbose`, the raw uevent file too
+#[instrument(skip(config))]
+pub fn print(config: &Config, verbose: bool) -> Result<()> {
+    let supplies = list()?;
+    if supplies.is_empty() {
+        println!("no power supply found in {SYS_PATH}");
+        return Ok(());
+    }
+    let used = match &config.bat_name {
+        Some(name) => Some((name.clone(), "set by bat_name")),
+        None => find_battery()?.map(|name| (name, "first entry named bat*")),
+    };
+    let yes_no = |flag: Option<bool>| flag.map(|f| if f { "yes" } else { "no" }.to_string());
+    for (i, supply) in supplies.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        match &used {
+            Some((name, reason)) if *name == supply.name => {
+                println!("{} (used by bato, {reason})", supply.name)
+            }
+            _ => println!("{}", supply.name),
+        }
+        let (now, full) = supply.attributes(config.full_design).unzip();
+        let rows = [
+            ("type", supply.kind.clone()),
+            ("scope", supply.scope.clone()),
+            ("present", yes_no(supply.present)),
+            ("online", yes_no(supply.online)),
+            ("status", supply.status.clone()),
+            ("capacity", supply.capacity.map(|c| format!("{c}%"))),
+            ("unit", supply.unit_name().map(String::from)),
+            ("now", now),
+            ("full", full),
+        ];
+        for (name, value) in rows {
+            if let Some(value) = value {
+                println!("  {name:<9} {value}");
+            }
+        }
+        if verbose {
+            println!("  uevent");
+            for line in supply.uevent.lines() {
+                println!("    {line}");
+            }
+        }
+    }
+    Ok(())
+}
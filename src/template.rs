@@ -0,0 +1,197 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::fmt::Write;
+use tracing::warn;
+
+// rendered in place of a value that is not available, eg. the time
+// remaining when the driver does not report the drain rate
+const MISSING_VALUE: &str = "?";
+
+/// A value that can be substituted in a template
+#[derive(Debug, Clone)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+#[derive(Debug, Default)]
+struct Spec {
+    fill: Option<char>,
+    align: Option<char>,
+    zero: bool,
+    width: usize,
+    precision: Option<usize>,
+}
+
+// parse a format spec `[[fill]align][0][width][.precision]`
+fn parse_spec(spec: &str) -> Option<Spec> {
+    let mut result = Spec::default();
+    let chars: Vec<char> = spec.chars().collect();
+    let mut i = 0;
+    let is_align = |c: &char| matches!(c, '<' | '^' | '>');
+    if chars.len() >= 2 && is_align(&chars[1]) {
+        result.fill = Some(chars[0]);
+        result.align = Some(chars[1]);
+        i = 2;
+    } else if chars.first().is_some_and(is_align) {
+        result.align = Some(chars[0]);
+        i = 1;
+    }
+    if chars.get(i) == Some(&'0') {
+        result.zero = true;
+        i += 1;
+    }
+    let rest: String = chars[i..].iter().collect();
+    let (width, precision) = match rest.split_once('.') {
+        Some((w, p)) => (w, Some(p.parse().ok()?)),
+        None => (rest.as_str(), None),
+    };
+    if !width.is_empty() {
+        result.width = width.parse().ok()?;
+    }
+    result.precision = precision;
+    Some(result)
+}
+
+fn format_value(value: Option<&Value>, spec: &Spec) -> String {
+    let text = match (value, spec.precision) {
+        (None, _) => MISSING_VALUE.to_string(),
+        (Some(Value::Int(i)), None) => i.to_string(),
+        (Some(Value::Int(i)), Some(p)) => format!("{:.p$}", *i as f64),
+        (Some(Value::Float(f)), None) => format!("{f:.1}"),
+        (Some(Value::Float(f)), Some(p)) => format!("{f:.p$}"),
+        (Some(Value::Str(s)), None) => s.clone(),
+        (Some(Value::Str(s)), Some(p)) => s.chars().take(p).collect(),
+    };
+    let len = text.chars().count();
+    if len >= spec.width {
+        return text;
+    }
+    let pad = spec.width - len;
+    let numeric = matches!(value, Some(Value::Int(_) | Value::Float(_)));
+    if spec.zero && numeric && spec.align.is_none() {
+        let (sign, digits) = match text.strip_prefix('-') {
+            Some(d) => ("-", d),
+            None => ("", text.as_str()),
+        };
+        return format!("{sign}{}{digits}", "0".repeat(pad));
+    }
+    let fill = spec.fill.unwrap_or(' ').to_string();
+    // like std::fmt, numbers are right aligned and strings left aligned
+    let align = spec.align.unwrap_or(if numeric { '>' } else { '<' });
+    match align {
+        '>' => format!("{}{text}", fill.repeat(pad)),
+        '^' => format!(
+            "{}{text}{}",
+            fill.repeat(pad / 2),
+            fill.repeat(pad - pad / 2)
+        ),
+        _ => format!("{text}{}", fill.repeat(pad)),
+    }
+}
+
+/// Render a template, substituting the `{name}` and `{name:spec}`
+/// placeholders with the values given by `lookup`
+///
+/// The spec follows `std::fmt`: `[[fill]align][0][width][.precision]`.
+/// Use `{{` and `}}` for literal braces. Unknown placeholders are kept as
+/// is, unavailable values are rendered as `?`.
+pub fn render<F>(template: &str, lookup: F) -> String
+where
+    F: Fn(&str) -> Option<Option<Value>>,
+{
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        output.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        if tail.starts_with("{{") || tail.starts_with("}}") {
+            output.push_str(&tail[..1]);
+            rest = &tail[2..];
+            continue;
+        }
+        let Some(end) = tail.find('}').filter(|_| tail.starts_with('{')) else {
+            output.push_str(&tail[..1]);
+            rest = &tail[1..];
+            continue;
+        };
+        let placeholder = &tail[1..end];
+        rest = &tail[end + 1..];
+        let (name, spec) = placeholder.split_once(':').unwrap_or((placeholder, ""));
+        let Some(spec) = parse_spec(spec) else {
+            warn!("invalid format spec in placeholder {{{placeholder}}}");
+            write!(output, "{{{placeholder}}}").ok();
+            continue;
+        };
+        match lookup(name.trim()) {
+            Some(value) => output.push_str(&format_value(value.as_ref(), &spec)),
+            None => {
+                warn!("unknown placeholder {{{name}}}");
+                write!(output, "{{{placeholder}}}").ok();
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<Option<Value>> {
+        match name {
+            "level" => Some(Some(Value::Int(7))),
+            "power_w" => Some(Some(Value::Float(3.25481))),
+            "status" => Some(Some(Value::Str("Charging".to_string()))),
+            "time_remaining" => Some(None),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn escaped_braces() {
+        assert_eq!(render("{{level}} }}{{", lookup), "{level} }{");
+        assert_eq!(render("{{{level}}}", lookup), "{7}");
+    }
+
+    #[test]
+    fn width_and_alignment() {
+        assert_eq!(render("{level:>3}%", lookup), "  7%");
+        assert_eq!(render("[{status:*^10}]", lookup), "[*Charging*]");
+        assert_eq!(render("[{status:10}]", lookup), "[Charging  ]");
+    }
+
+    #[test]
+    fn zero_padding_and_precision() {
+        assert_eq!(render("{power_w:05.1}", lookup), "003.3");
+        assert_eq!(render("{power_w:.3}", lookup), "3.255");
+        assert_eq!(render("{power_w}", lookup), "3.3");
+        assert_eq!(render("{level:03}", lookup), "007");
+    }
+
+    #[test]
+    fn missing_and_unknown_placeholders() {
+        assert_eq!(render("{time_remaining:>3}", lookup), "  ?");
+        assert_eq!(render("{nope} {level}", lookup), "{nope} 7");
+        assert_eq!(render("{level:x}", lookup), "{level:x}");
+        assert_eq!(render("unclosed {level", lookup), "unclosed {level");
+    }
+
+    #[test]
+    fn spec() {
+        let spec = parse_spec("*^08.2").unwrap();
+        assert_eq!(spec.fill, Some('*'));
+        assert_eq!(spec.align, Some('^'));
+        assert!(spec.zero);
+        assert_eq!(spec.width, 8);
+        assert_eq!(spec.precision, Some(2));
+        let spec = parse_spec("").unwrap();
+        assert_eq!((spec.align, spec.width, spec.precision), (None, 0, None));
+        assert!(parse_spec("3.x").is_none());
+        assert!(parse_spec("abc").is_none());
+    }
+}
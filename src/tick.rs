@@ -0,0 +1,161 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use std::time::Duration;
+use tracing::{instrument, trace};
+
+use crate::Config;
+use crate::fsm::{Data, PsStatus};
+
+// distance to a threshold, in percent, under which bato polls at the
+// minimum tick rate
+const NEAR_THRESHOLD: u32 = 2;
+// how many polls should happen before reaching the next threshold
+const POLLS_PER_THRESHOLD: f64 = 2.;
+
+/// Compute the timeout of the next poll
+///
+/// With `adaptive_tick` disabled, this is always `tick_rate`. Otherwise,
+/// bato polls fast when close to a threshold or when the estimated time
+/// to reach it is short, and slowly when charging or full.
+#[instrument(skip_all)]
+pub fn next_tick(config: &Config, data: Option<&Data>) -> Duration {
+    let tick_rate = config.tick_rate as u64;
+    if !config.adaptive_tick {
+        return Duration::from_secs(tick_rate);
+    }
+    let min = config.min_tick_rate.min(config.max_tick_rate) as u64;
+    let max = config.max_tick_rate as u64;
+    let Some(data) = data else {
+        return Duration::from_secs(tick_rate.clamp(min, max));
+    };
+
+    let secs = match data.status {
+        PsStatus::Discharging => {
+            let level = data.current_level;
+            let threshold = [config.low_level, config.critical_level]
+                .into_iter()
+                .find(|t| level > *t);
+            match (threshold, data.rate) {
+                // below the critical level, nothing more to notify
+                (None, _) => tick_rate,
+                (Some(t), _) if level - t <= NEAR_THRESHOLD => min,
+                (Some(t), Some(rate)) => {
+                    let eta = (level - t) as f64 / rate * 3600.;
+                    trace!("next threshold {t}% in {eta:.0}s");
+                    (eta / POLLS_PER_THRESHOLD) as u64
+                }
+                (Some(_), None) => tick_rate,
+            }
+        }
+        PsStatus::Charging | PsStatus::Full | PsStatus::NotCharging => max,
+        PsStatus::Unknown => tick_rate,
+    };
+    Duration::from_secs(secs.clamp(min, max))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(adaptive_tick: bool) -> Config {
+        Config {
+            tick_rate: 30,
+            adaptive_tick,
+            min_tick_rate: 5,
+            max_tick_rate: 120,
+            low_level: 20,
+            critical_level: 5,
+            ..Config::builtin().unwrap()
+        }
+    }
+
+    fn data(status: PsStatus, level: u32, rate: Option<f64>) -> Data {
+        Data {
+            battery: "BAT0".to_string(),
+            current_level: level,
+            status,
+            rate,
+            power: None,
+            health: None,
+        }
+    }
+
+    fn secs(config: &Config, data: Option<&Data>) -> u64 {
+        next_tick(config, data).as_secs()
+    }
+
+    #[test]
+    fn fixed_tick() {
+        let config = config(false);
+        let near = data(PsStatus::Discharging, 21, Some(10.));
+        assert_eq!(secs(&config, Some(&near)), 30);
+        assert_eq!(secs(&config, None), 30);
+    }
+
+    #[test]
+    fn tick_rate_clamped_without_data() {
+        let mut config = config(true);
+        assert_eq!(secs(&config, None), 30);
+        config.tick_rate = 500;
+        assert_eq!(secs(&config, None), 120);
+        config.tick_rate = 1;
+        assert_eq!(secs(&config, None), 5);
+    }
+
+    #[test]
+    fn slow_when_charging_or_full() {
+        let config = config(true);
+        for status in [PsStatus::Charging, PsStatus::Full, PsStatus::NotCharging] {
+            assert_eq!(secs(&config, Some(&data(status, 50, Some(10.)))), 120);
+        }
+    }
+
+    #[test]
+    fn fast_near_a_threshold() {
+        let config = config(true);
+        assert_eq!(
+            secs(&config, Some(&data(PsStatus::Discharging, 22, None))),
+            5
+        );
+        assert_eq!(
+            secs(&config, Some(&data(PsStatus::Discharging, 7, Some(1.)))),
+            5
+        );
+    }
+
+    #[test]
+    fn eta_clamped_to_the_bounds() {
+        let config = config(true);
+        // 10% to the low level at 10%/h, 1h away, polled twice: 1800s
+        let far = data(PsStatus::Discharging, 30, Some(10.));
+        assert_eq!(secs(&config, Some(&far)), 120);
+        // 8% at 100%/h, 288s away: 144s, above the maximum
+        let fast = data(PsStatus::Discharging, 28, Some(100.));
+        assert_eq!(secs(&config, Some(&fast)), 120);
+        // 5% at 600%/h, 30s away: 15s
+        let faster = data(PsStatus::Discharging, 25, Some(600.));
+        assert_eq!(secs(&config, Some(&faster)), 15);
+        // 3% at 3600%/h: 1s, below the minimum
+        let fastest = data(PsStatus::Discharging, 23, Some(3600.));
+        assert_eq!(secs(&config, Some(&fastest)), 5);
+    }
+
+    #[test]
+    fn below_critical_uses_the_tick_rate() {
+        let config = config(true);
+        let empty = data(PsStatus::Discharging, 3, Some(10.));
+        assert_eq!(secs(&config, Some(&empty)), 30);
+    }
+
+    #[test]
+    fn min_above_max() {
+        let mut config = config(true);
+        config.min_tick_rate = 200;
+        assert_eq!(
+            secs(&config, Some(&data(PsStatus::Discharging, 22, None))),
+            120
+        );
+    }
+}
@@ -2,7 +2,9 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::{env, fs};
 
 use anyhow::Result;
@@ -19,6 +21,9 @@ const LOG_DIR: &str = "bato";
 const LOG_FILE: &str = "bato.log";
 const LOG_FILE_OLD: &str = "bato.old.log";
 
+// whether the `stdout` logs go to stderr
+static STDERR: AtomicBool = AtomicBool::new(false);
+
 fn rotate_log_file(log_dir: PathBuf) -> Result<Option<PathBuf>> {
     let log_file = log_dir.join(LOG_FILE);
     if log_file.is_file() {
@@ -34,6 +39,12 @@ fn rotate_log_file(log_dir: PathBuf) -> Result<Option<PathBuf>> {
     Ok(None)
 }
 
+/// Send the `stdout` logs to stderr from now on, to keep stdout for the
+/// output of bato, eg. the notifications of the stdout backend
+pub fn logs_to_stderr() {
+    STDERR.store(true, Ordering::Relaxed);
+}
+
 fn get_filter(level: &Option<LogLevel>) -> LevelFilter {
     match level {
         Some(LogLevel::Trace) => LevelFilter::TRACE,
@@ -56,9 +67,19 @@ pub fn init(cli: &Cli) -> Result<Option<WorkerGuard>> {
 
     match cli.logs {
         Logs::Stdout => {
+            // keep stdout for the output of the subcommands, eg. `status --json`
+            if cli.command.is_some() {
+                logs_to_stderr();
+            }
             tracing_subscriber::fmt()
                 .with_env_filter(filter)
                 .compact()
+                .with_writer(|| -> Box<dyn Write> {
+                    match STDERR.load(Ordering::Relaxed) {
+                        true => Box::new(io::stderr()),
+                        false => Box::new(io::stdout()),
+                    }
+                })
                 .init();
             Ok(None)
         }
@@ -3,6 +3,7 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use anyhow::{Context, Result, anyhow, bail};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::{fs, path::PathBuf};
 use tracing::{debug, error, instrument};
 
@@ -32,3 +33,62 @@ pub fn check_dir(path: &str) -> Result<()> {
     }
     Ok(())
 }
+
+/// Current time as a unix timestamp, in seconds
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// A local time, as needed to match time ranges
+#[derive(Debug, Clone, Copy)]
+pub struct LocalTime {
+    /// Day of the week, 0 is sunday
+    pub weekday: u32,
+    /// Minutes since midnight
+    pub minutes: u32,
+}
+
+/// Convert a unix timestamp, in seconds, to the local time
+pub fn local_time(timestamp: u64) -> LocalTime {
+    let time = timestamp as libc::time_t;
+    // SAFETY: all-zero is a valid `tm`
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    // SAFETY: both pointers are valid for the duration of the call,
+    // localtime_r is the thread safe variant
+    unsafe { libc::localtime_r(&time, &mut tm) };
+    LocalTime {
+        weekday: tm.tm_wday as u32,
+        minutes: (tm.tm_hour * 60 + tm.tm_min) as u32,
+    }
+}
+
+/// Format a duration for humans, `1h05` or `22 min`
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.as_secs() / 60;
+    match minutes / 60 {
+        0 => format!("{minutes} min"),
+        hours => format!("{hours}h{:02}", minutes % 60),
+    }
+}
+
+/// The edit distance between two strings
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = match ca == *cb {
+                true => diagonal,
+                false => 1 + diagonal.min(above).min(row[j]),
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}